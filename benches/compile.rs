@@ -0,0 +1,80 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lhscript::{compile, Scannable, TokenStream};
+use std::hint::black_box;
+
+/// A script of the given number of blocks, each using most of the syntax
+fn generate(blocks: usize) -> String {
+    let mut source = String::new();
+    for i in 0..blocks {
+        source.push_str(&format!(
+            "// Block {i}\n\
+             fn f{i}(a, b) {{\n    \
+                 let sum = 0;\n    \
+                 for (let j = 0; j < a; j++) {{\n        \
+                     if (j % 2 == 0 && b) sum = sum + j * {i}.5; else sum = sum - (null ?? 1);\n    \
+                 }}\n    \
+                 return [sum, {{\"key\": (x) -> x ** 2}}][0];\n\
+             }}\n\
+             print f{i}({i}, true);\n"
+        ));
+    }
+
+    source
+}
+
+/// Short snippets of code, like the lines typed into a REPL
+fn snippets(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("let x{i} = f({i}, \"text\") + {i}.5; // line {i}"))
+        .collect()
+}
+
+fn scan_and_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compile");
+
+    for blocks in [100, 1000] {
+        let source = generate(blocks);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("scan", blocks), &source, |b, source| {
+            b.iter(|| source.as_str().tokens().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("scan and parse", blocks), &source, |b, source| {
+            b.iter(|| compile(source).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn reuse_stream(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reuse");
+    let snippets = snippets(1000);
+    group.throughput(Throughput::Elements(snippets.len() as u64));
+
+    group.bench_function("fresh streams", |b| {
+        b.iter(|| {
+            for snippet in &snippets {
+                for token in TokenStream::new(snippet) {
+                    black_box(token.unwrap());
+                }
+            }
+        })
+    });
+    group.bench_function("reset_with", |b| {
+        let mut stream = TokenStream::new("");
+        b.iter(|| {
+            for snippet in &snippets {
+                stream.reset_with(snippet);
+                for token in stream.by_ref() {
+                    black_box(token.unwrap());
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scan_and_parse, reuse_stream);
+criterion_main!(benches);
@@ -0,0 +1,344 @@
+use super::types::*;
+use crate::errors::ScriptError;
+use crate::token::Token;
+
+/// Precedence of assignments, binds looser than any operator
+const ASSIGN_PRECEDENCE: u8 = 0;
+
+/// Precedence of power, the tightest binding binary operator
+const POWER_PRECEDENCE: u8 = 8;
+
+/// Precedence of unary expressions, binds tighter than any binary operator
+const UNARY_PRECEDENCE: u8 = 9;
+
+/// Precedence of calls, binds tighter than unary expressions
+const CALL_PRECEDENCE: u8 = 10;
+
+/// Precedence of literals and groupings, never needs parentheses
+const PRIMARY_PRECEDENCE: u8 = 11;
+
+/// Escape text for a string literal, so it scans back to the same text. Like
+/// quoting for JSON, but with the escape sequences of the language
+pub(crate) fn escape(str: &str) -> String {
+    let mut out = String::new();
+    for c in str.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '$' => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Prints expressions in conventional infix form, only adding the parentheses
+/// required by operator precedence and associativity
+pub struct InfixPrinter;
+
+impl InfixPrinter {
+    pub fn print(&mut self, expression: Expression) -> Result<String, ScriptError> {
+        expression.accept(self)
+    }
+
+    /// Binding strength of a binary or logical operator, higher binds tighter
+    fn binary_precedence(operator: &Token) -> u8 {
+        match operator {
+            Token::QuestionQuestion => 1,
+            Token::Or => 2,
+            Token::And => 3,
+            Token::EqualEqual | Token::BangEqual => 4,
+            Token::Greater | Token::GreaterEqual | Token::Less | Token::LessEqual => 5,
+            Token::Plus | Token::Minus => 6,
+            Token::StarStar => POWER_PRECEDENCE,
+            _ => 7,
+        }
+    }
+
+    /// Binding strength of an expression, higher binds tighter
+    fn precedence(expression: &Expression) -> u8 {
+        match expression {
+            Expression::Binary(binary) => Self::binary_precedence(&binary.operator.token),
+            Expression::Logical(logical) => Self::binary_precedence(&logical.operator.token),
+            Expression::Coalesce(coalesce) => Self::binary_precedence(&coalesce.operator.token),
+            Expression::Unary(_) => UNARY_PRECEDENCE,
+            Expression::Assign(_) | Expression::Lambda(_) => ASSIGN_PRECEDENCE,
+            Expression::Call(_) | Expression::Index(_) | Expression::Get(_) | Expression::Increment(_) => CALL_PRECEDENCE,
+            _ => PRIMARY_PRECEDENCE,
+        }
+    }
+
+    /// Print an operand, wrapping it in parentheses if it binds looser than required
+    fn operand(&mut self, expression: &Expression, minimum: u8) -> Result<String, ScriptError> {
+        let out = expression.accept(self)?;
+        if Self::precedence(expression) < minimum {
+            Ok(format!("({})", out))
+        } else {
+            Ok(out)
+        }
+    }
+
+    fn operator(token: &Token) -> Result<&'static str, ScriptError> {
+        Ok(match token {
+            Token::EqualEqual => "==",
+            Token::BangEqual => "!=",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::StarStar => "**",
+            Token::PlusPlus => "++",
+            Token::MinusMinus => "--",
+            Token::Bang => "!",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::QuestionQuestion => "??",
+            _ => return Err(ScriptError::AstPrinterError),
+        })
+    }
+}
+
+impl ExpressionVisitor<String> for InfixPrinter {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<String, ScriptError> {
+        let operator = Self::operator(&expr.operator.token)?;
+        let right = self.operand(&expr.right, UNARY_PRECEDENCE)?;
+
+        // Repeated operators are kept apart, - -x would scan back as --x
+        if right.starts_with(operator) {
+            return Ok(format!("{} {}", operator, right));
+        }
+
+        Ok(format!("{}{}", operator, right))
+    }
+
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<String, ScriptError> {
+        let operator = Self::operator(&expr.operator.token)?;
+        let precedence = Self::binary_precedence(&expr.operator.token);
+
+        // Power is right associative, all other binary operators are left
+        // associative. The operand on the other side of the associativity
+        // needs parentheses already at equal precedence
+        let (left, right) = if expr.operator.token == Token::StarStar {
+            (precedence + 1, precedence)
+        } else {
+            (precedence, precedence + 1)
+        };
+        let left = self.operand(&expr.left, left)?;
+        let right = self.operand(&expr.right, right)?;
+
+        Ok(format!("{} {} {}", left, operator, right))
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<String, ScriptError> {
+        let operator = Self::operator(&expr.operator.token)?;
+        let precedence = Self::binary_precedence(&expr.operator.token);
+
+        let left = self.operand(&expr.left, precedence)?;
+        let right = self.operand(&expr.right, precedence + 1)?;
+
+        Ok(format!("{} {} {}", left, operator, right))
+    }
+
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<String, ScriptError> {
+        let precedence = Self::binary_precedence(&expr.operator.token);
+
+        let left = self.operand(&expr.left, precedence)?;
+        let right = self.operand(&expr.right, precedence + 1)?;
+
+        Ok(format!("{} ?? {}", left, right))
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<String, ScriptError> {
+        Ok(format!("({})", expr.group.accept(self)?))
+    }
+
+    fn visit_literal(&mut self, expr: &LiteralExpression) -> Result<String, ScriptError> {
+        match &expr.literal {
+            Token::String(str) => Ok(format!("\"{}\"", escape(str))),
+            Token::Number(nbr) => Ok(nbr.to_string()),
+            Token::Integer(nbr) => Ok(nbr.to_string()),
+            Token::True => Ok(String::from("true")),
+            Token::False => Ok(String::from("false")),
+            Token::Null => Ok(String::from("null")),
+            _ => Err(ScriptError::AstPrinterError),
+        }
+    }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<String, ScriptError> {
+        Ok(expr.name.clone())
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<String, ScriptError> {
+        // Assignment is right associative, so the value never needs parentheses
+        let value = self.operand(&expr.value, ASSIGN_PRECEDENCE)?;
+
+        Ok(format!("{} = {}", expr.name, value))
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<String, ScriptError> {
+        Ok(format!("{}{}", expr.name, Self::operator(&expr.operator.token)?))
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<String, ScriptError> {
+        let callee = self.operand(&expr.callee, CALL_PRECEDENCE)?;
+        let arguments = expr.arguments.iter()
+            .map(|argument| argument.accept(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(format!("{}({})", callee, arguments.join(", ")))
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<String, ScriptError> {
+        let elements = expr.elements.iter()
+            .map(|element| element.accept(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(format!("[{}]", elements.join(", ")))
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<String, ScriptError> {
+        // Text is escaped, anything else is embedded in ${}
+        let mut out = String::from('"');
+        for part in &expr.parts {
+            match part {
+                Expression::Literal(LiteralExpression { literal: Token::String(text) }) => out.push_str(&escape(text)),
+                part => out.push_str(&format!("${{{}}}", part.accept(self)?)),
+            }
+        }
+        out.push('"');
+
+        Ok(out)
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<String, ScriptError> {
+        let target = self.operand(&expr.target, CALL_PRECEDENCE)?;
+        Ok(format!("{}[{}]", target, expr.index.accept(self)?))
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<String, ScriptError> {
+        let object = self.operand(&expr.object, CALL_PRECEDENCE)?;
+        Ok(format!("{}.{}", object, expr.name))
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<String, ScriptError> {
+        let entries = expr.entries.iter()
+            .map(|(key, value)| Ok(format!("{}: {}", key.accept(self)?, value.accept(self)?)))
+            .collect::<Result<Vec<_>, ScriptError>>()?;
+
+        Ok(format!("{{{}}}", entries.join(", ")))
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<String, ScriptError> {
+        Ok(format!("({}) -> {}", expr.params.join(", "), expr.body.accept(self)?))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<String, ScriptError> {
+        // Only a block of nothing but its value can be written as an expression
+        match (&expr.statements[..], &expr.tail) {
+            ([], Some(tail)) => Ok(format!("{{ {} }}", tail.accept(self)?)),
+            _ => Err(ScriptError::AstPrinterError),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenMetadata;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    fn parse(code: &str) -> Expression {
+        let tokens = code.tokens().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn minimal_parentheses() {
+        // (* 2 (- 4 1)) without any grouping nodes
+        let expr = Expression::Binary(BinaryExpression {
+            left: Box::new(Expression::Literal(LiteralExpression { literal: Token::Number(2f64) })),
+            operator: TokenMetadata::synthetic(Token::Star),
+            right: Box::new(Expression::Binary(BinaryExpression {
+                left: Box::new(Expression::Literal(LiteralExpression { literal: Token::Number(4f64) })),
+                operator: TokenMetadata::synthetic(Token::Minus),
+                right: Box::new(Expression::Literal(LiteralExpression { literal: Token::Number(1f64) })),
+            })),
+        });
+
+        let mut printer = InfixPrinter;
+        assert_eq!(printer.print(expr).unwrap(), "2 * (4 - 1)");
+    }
+
+    #[test]
+    fn associativity() {
+        let mut printer = InfixPrinter;
+
+        assert_eq!(printer.print(parse("1 - 2 - 3")).unwrap(), "1 - 2 - 3");
+        assert_eq!(printer.print(parse("1 - (2 - 3)")).unwrap(), "1 - (2 - 3)");
+        assert_eq!(printer.print(parse("-(1 + 2) * 3 < 4")).unwrap(), "-(1 + 2) * 3 < 4");
+        assert_eq!(printer.print(parse("(a || b) && c")).unwrap(), "(a || b) && c");
+        assert_eq!(printer.print(parse("a = b = 1 + 2")).unwrap(), "a = b = 1 + 2");
+        assert_eq!(printer.print(parse("-f(1, 2 * 3)()")).unwrap(), "-f(1, 2 * 3)()");
+        assert_eq!(printer.print(parse("2 ** 3 ** 2 * 4")).unwrap(), "2 ** 3 ** 2 * 4");
+        assert_eq!(printer.print(parse("-[1, a][0][b + 1]")).unwrap(), "-[1, a][0][b + 1]");
+        assert_eq!(printer.print(parse("{\"a\": 1, \"b\": {}}[\"a\"]")).unwrap(), "{\"a\": 1, \"b\": {}}[\"a\"]");
+    }
+
+    #[test]
+    fn round_trip() {
+        let code = "2 * (4 - 1.5) == -3 / \"x\"";
+
+        let mut printer = InfixPrinter;
+        let printed = printer.print(parse(code)).unwrap();
+
+        assert_eq!(printed, code);
+        assert_eq!(parse(&printed), parse(code));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let mut printer = InfixPrinter;
+
+        for code in [r#""a\"b""#, r#""a\nb""#, r#""\\ \$ \t""#] {
+            let printed = printer.print(parse(code)).unwrap();
+            assert_eq!(printed, code);
+            assert_eq!(printer.print(parse(&printed)).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut printer = InfixPrinter;
+
+        let code = r#""say \"${x + 1}\" for \$5 \\ \n\t${y}""#;
+        let printed = printer.print(parse(code)).unwrap();
+        assert_eq!(printed, code);
+        assert_eq!(printer.print(parse(&printed)).unwrap(), code);
+    }
+
+    #[test]
+    fn repeated_unary() {
+        let mut printer = InfixPrinter;
+
+        // Compared as trees, the positions differ with the spacing
+        let tree = |code: &str| crate::ast::AstPrinter::new().print(parse(code)).unwrap();
+        for code in ["- -x", "!!x", "-(-x)", "- - -1"] {
+            let printed = printer.print(parse(code)).unwrap();
+            assert_eq!(tree(&printed), tree(code), "{} printed as {}", code, printed);
+        }
+        assert_eq!(printer.print(parse("- -x")).unwrap(), "- -x");
+        assert_eq!(printer.print(parse("-!x")).unwrap(), "-!x");
+    }
+}
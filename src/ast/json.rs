@@ -0,0 +1,275 @@
+use super::statements::*;
+use super::types::*;
+use crate::errors::ScriptError;
+use crate::scanner::Position;
+use crate::token::Token;
+
+/// Prints expressions and statements as JSON trees, each node an object
+/// with a "type" field, for tooling and for diffing parser output
+pub struct JsonPrinter;
+
+impl JsonPrinter {
+    pub fn print(&mut self, expression: Expression) -> Result<String, ScriptError> {
+        expression.accept(self)
+    }
+
+    /// Print a whole program as an array of statements
+    pub fn print_program(&mut self, statements: &[Statement]) -> Result<String, ScriptError> {
+        self.array(statements)
+    }
+
+    fn array(&mut self, statements: &[Statement]) -> Result<String, ScriptError> {
+        let mut elements = vec![];
+        for statement in statements {
+            elements.push(statement.accept(self)?);
+        }
+
+        Ok(format!("[{}]", elements.join(",")))
+    }
+
+    fn optional(&mut self, expression: Option<&Expression>) -> Result<String, ScriptError> {
+        match expression {
+            Some(expression) => expression.accept(self),
+            None => Ok(String::from("null")),
+        }
+    }
+
+    fn operator(token: &Token) -> Result<&'static str, ScriptError> {
+        Ok(match token {
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::StarStar => "**",
+            Token::PlusPlus => "++",
+            Token::MinusMinus => "--",
+            Token::EqualEqual => "==",
+            Token::BangEqual => "!=",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            Token::Bang => "!",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::QuestionQuestion => "??",
+            _ => return Err(ScriptError::AstPrinterError),
+        })
+    }
+}
+
+/// Quote a string, escaping what JSON doesn't allow in one
+pub(crate) fn quote(str: &str) -> String {
+    let mut out = String::from('"');
+    for c in str.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+impl ExpressionVisitor<String> for JsonPrinter {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<String, ScriptError> {
+        let op = Self::operator(&expr.operator.token)?;
+        Ok(format!(r#"{{"type":"unary","op":"{}","right":{}}}"#, op, expr.right.accept(self)?))
+    }
+
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<String, ScriptError> {
+        let op = Self::operator(&expr.operator.token)?;
+        Ok(format!(r#"{{"type":"binary","op":"{}","left":{},"right":{}}}"#, op, expr.left.accept(self)?, expr.right.accept(self)?))
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<String, ScriptError> {
+        let op = Self::operator(&expr.operator.token)?;
+        Ok(format!(r#"{{"type":"logical","op":"{}","left":{},"right":{}}}"#, op, expr.left.accept(self)?, expr.right.accept(self)?))
+    }
+
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"coalesce","left":{},"right":{}}}"#, expr.left.accept(self)?, expr.right.accept(self)?))
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"grouping","expression":{}}}"#, expr.group.accept(self)?))
+    }
+
+    fn visit_literal(&mut self, expr: &LiteralExpression) -> Result<String, ScriptError> {
+        let value = match &expr.literal {
+            Token::String(str) => quote(str),
+            Token::Number(nbr) => nbr.to_string(),
+            Token::Integer(nbr) => nbr.to_string(),
+            Token::True => String::from("true"),
+            Token::False => String::from("false"),
+            Token::Null => String::from("null"),
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        Ok(format!(r#"{{"type":"literal","value":{}}}"#, value))
+    }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"variable","name":{}}}"#, quote(&expr.name)))
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"assign","name":{},"value":{}}}"#, quote(&expr.name), expr.value.accept(self)?))
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<String, ScriptError> {
+        let op = Self::operator(&expr.operator.token)?;
+        Ok(format!(r#"{{"type":"increment","op":"{}","name":{}}}"#, op, quote(&expr.name)))
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<String, ScriptError> {
+        let mut arguments = vec![];
+        for argument in &expr.arguments {
+            arguments.push(argument.accept(self)?);
+        }
+
+        Ok(format!(r#"{{"type":"call","callee":{},"arguments":[{}]}}"#, expr.callee.accept(self)?, arguments.join(",")))
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<String, ScriptError> {
+        let mut elements = vec![];
+        for element in &expr.elements {
+            elements.push(element.accept(self)?);
+        }
+
+        Ok(format!(r#"{{"type":"array","elements":[{}]}}"#, elements.join(",")))
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<String, ScriptError> {
+        let mut parts = vec![];
+        for part in &expr.parts {
+            parts.push(part.accept(self)?);
+        }
+
+        Ok(format!(r#"{{"type":"interpolation","parts":[{}]}}"#, parts.join(",")))
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"index","target":{},"index":{}}}"#, expr.target.accept(self)?, expr.index.accept(self)?))
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"get","object":{},"name":{}}}"#, expr.object.accept(self)?, quote(&expr.name)))
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<String, ScriptError> {
+        let mut entries = vec![];
+        for (key, value) in &expr.entries {
+            entries.push(format!(r#"{{"key":{},"value":{}}}"#, key.accept(self)?, value.accept(self)?));
+        }
+
+        Ok(format!(r#"{{"type":"map","entries":[{}]}}"#, entries.join(",")))
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<String, ScriptError> {
+        let params: Vec<String> = expr.params.iter().map(|param| quote(param)).collect();
+        Ok(format!(r#"{{"type":"lambda","params":[{}],"body":{}}}"#, params.join(","), expr.body.accept(self)?))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<String, ScriptError> {
+        let statements = self.array(&expr.statements)?;
+        Ok(format!(r#"{{"type":"block","statements":{},"value":{}}}"#, statements, self.optional(expr.tail.as_deref())?))
+    }
+}
+
+impl StatementVisitor<String> for JsonPrinter {
+    fn visit_expression_statement(&mut self, expression: &Expression) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"expression","expression":{}}}"#, expression.accept(self)?))
+    }
+
+    fn visit_print(&mut self, expressions: &[Expression]) -> Result<String, ScriptError> {
+        let mut values = vec![];
+        for expression in expressions {
+            values.push(expression.accept(self)?);
+        }
+
+        Ok(format!(r#"{{"type":"print","expressions":[{}]}}"#, values.join(",")))
+    }
+
+    fn visit_assert(&mut self, condition: &Expression, message: Option<&Expression>, _position: Position) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"assert","condition":{},"message":{}}}"#, condition.accept(self)?, self.optional(message)?))
+    }
+
+    fn visit_include(&mut self, path: &str, _position: Position) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"include","path":{}}}"#, quote(path)))
+    }
+
+    fn visit_let(&mut self, name: &str, initializer: Option<&Expression>) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"let","name":{},"initializer":{}}}"#, quote(name), self.optional(initializer)?))
+    }
+
+    fn visit_if(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) -> Result<String, ScriptError> {
+        let else_branch = match else_branch {
+            Some(statement) => statement.accept(self)?,
+            None => String::from("null"),
+        };
+
+        Ok(format!(r#"{{"type":"if","condition":{},"then":{},"else":{}}}"#, condition.accept(self)?, then_branch.accept(self)?, else_branch))
+    }
+
+    fn visit_while(&mut self, condition: &Expression, body: &Statement, increment: Option<&Expression>) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"while","condition":{},"body":{},"increment":{}}}"#, condition.accept(self)?, body.accept(self)?, self.optional(increment)?))
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"block","statements":{}}}"#, self.array(statements)?))
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<String, ScriptError> {
+        let params: Vec<String> = params.iter().map(|param| quote(param)).collect();
+        Ok(format!(r#"{{"type":"function","name":{},"params":[{}],"body":{}}}"#, quote(name), params.join(","), self.array(body)?))
+    }
+
+    fn visit_return(&mut self, value: Option<&Expression>) -> Result<String, ScriptError> {
+        Ok(format!(r#"{{"type":"return","value":{}}}"#, self.optional(value)?))
+    }
+
+    fn visit_break(&mut self) -> Result<String, ScriptError> {
+        Ok(String::from(r#"{"type":"break"}"#))
+    }
+
+    fn visit_continue(&mut self) -> Result<String, ScriptError> {
+        Ok(String::from(r#"{"type":"continue"}"#))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    #[test]
+    fn print() {
+        let expr = Parser::new("1 + 2".tokens().unwrap()).parse().unwrap();
+        let json = JsonPrinter.print(expr.clone()).unwrap();
+
+        assert_eq!(json, r#"{"type":"binary","op":"+","left":{"type":"literal","value":1},"right":{"type":"literal","value":2}}"#);
+        // Printing the same tree again gives the very same output
+        assert_eq!(JsonPrinter.print(expr).unwrap(), json);
+    }
+
+    #[test]
+    fn print_program() {
+        let program = Parser::new("let s = \"a\\\"b\"; if (s) print -f(s); else {}".tokens().unwrap()).parse_program().unwrap();
+
+        assert_eq!(JsonPrinter.print_program(&program).unwrap(), concat!(
+            r#"[{"type":"let","name":"s","initializer":{"type":"literal","value":"a\"b"}},"#,
+            r#"{"type":"if","condition":{"type":"variable","name":"s"},"#,
+            r#""then":{"type":"print","expressions":[{"type":"unary","op":"-","right":{"type":"call","callee":{"type":"variable","name":"f"},"arguments":[{"type":"variable","name":"s"}]}}]},"#,
+            r#""else":{"type":"block","statements":[]}}]"#,
+        ));
+    }
+}
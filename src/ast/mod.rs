@@ -1,5 +1,17 @@
 mod printer;
 pub use printer::*;
 
+mod infix;
+pub use infix::*;
+
+mod rpn;
+pub use rpn::*;
+
+mod json;
+pub use json::*;
+
+mod statements;
+pub use statements::*;
+
 mod types;
 pub use types::*;
@@ -1,76 +1,323 @@
+use super::statements::*;
 use super::types::*;
 use crate::errors::{ScriptError};
+use crate::scanner::Position;
 use crate::token::Token;
 
-pub struct AstPrinter;
+/// Prints expressions and statements as parenthesized prefix notation,
+/// compact on a single line by default
+#[derive(Default)]
+pub struct AstPrinter {
+    /// Put each nested node on its own line, indented by its depth
+    pretty: bool,
+
+    /// Depth of the node currently being printed
+    depth: usize,
+}
 
 impl AstPrinter {
-    pub fn print(&self, expression: Expression) -> Result<String, ScriptError> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A printer putting each nested node on its own line
+    pub fn pretty() -> Self {
+        AstPrinter { pretty: true, depth: 0 }
+    }
+
+    pub fn print(&mut self, expression: Expression) -> Result<String, ScriptError> {
         expression.accept(self)
     }
 
-    fn parenthesize(&self, name: &str, expressions: &[&Expression]) -> Result<String, ScriptError> {
+    /// Print a whole program, one top level statement per line
+    pub fn print_program(&mut self, statements: &[Statement]) -> Result<String, ScriptError> {
+        let mut lines = vec![];
+        for statement in statements {
+            lines.push(statement.accept(self)?);
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Print a node one level deeper than the current one
+    fn nested(&mut self, node: impl FnOnce(&mut Self) -> Result<String, ScriptError>) -> Result<String, ScriptError> {
+        self.depth += 1;
+        let out = node(self);
+        self.depth -= 1;
+        out
+    }
+
+    /// Wrap a name and the already printed, nested, parts of a node in parentheses
+    fn join(&self, name: &str, parts: &[String]) -> String {
+        let separator = if self.pretty {
+            format!("\n{}", "  ".repeat(self.depth + 1))
+        } else {
+            String::from(" ")
+        };
+
         let mut out = String::from('(');
         out.push_str(name);
-        for expr in expressions {
-            out.push(' ');
-            out.push_str(&expr.accept(self)?)
+        for part in parts {
+            out.push_str(&separator);
+            out.push_str(part);
         }
 
         out.push(')');
-        Ok(out)
+        out
+    }
+
+    fn parenthesize(&mut self, name: &str, expressions: &[&Expression]) -> Result<String, ScriptError> {
+        let mut parts = vec![];
+        for expr in expressions {
+            parts.push(self.nested(|printer| expr.accept(printer))?);
+        }
+
+        Ok(self.join(name, &parts))
+    }
+
+    /// Print statements nested in the current node
+    fn statements(&mut self, statements: &[Statement]) -> Result<Vec<String>, ScriptError> {
+        let mut parts = vec![];
+        for statement in statements {
+            parts.push(self.nested(|printer| statement.accept(printer))?);
+        }
+
+        Ok(parts)
     }
 }
 
 impl ExpressionVisitor<String> for AstPrinter {
-    fn visit_unary(&self, expr: &UnaryExpression) -> Result<String, ScriptError> {
-        let name = match &expr.operator {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
             Token::Minus => "-",
+            Token::Bang => "!",
             _ => return Err(ScriptError::AstPrinterError),
         };
 
         self.parenthesize(name, &[&expr.right])
     }
 
-    fn visit_binary(&self, expr: &BinaryExpression) -> Result<String, ScriptError> {
-        let name = match &expr.operator {
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
             Token::Plus => "+",
             Token::Minus => "-",
             Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::StarStar => "**",
+            Token::EqualEqual => "==",
+            Token::BangEqual => "!=",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        self.parenthesize(name, &[&expr.left, &expr.right])
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
+            Token::And => "&&",
+            Token::Or => "||",
             _ => return Err(ScriptError::AstPrinterError),
         };
 
         self.parenthesize(name, &[&expr.left, &expr.right])
     }
 
-    fn visit_grouping(&self, expr: &GroupingExpression) -> Result<String, ScriptError> {
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<String, ScriptError> {
+        self.parenthesize("??", &[&expr.left, &expr.right])
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<String, ScriptError> {
         self.parenthesize("group", &[&expr.group])
     }
 
-    fn visit_literal(&self, expr: &LiteralExpression) -> Result<String, ScriptError> {
+    fn visit_literal(&mut self, expr: &LiteralExpression) -> Result<String, ScriptError> {
        match &expr.literal {
             Token::String(str) => Ok(String::from(str)),
             Token::Number(nbr) => Ok(nbr.to_string()),
+            Token::Integer(nbr) => Ok(nbr.to_string()),
+            Token::True => Ok(String::from("true")),
+            Token::False => Ok(String::from("false")),
+            Token::Null => Ok(String::from("null")),
             _ => Err(ScriptError::AstPrinterError),
        }
     }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<String, ScriptError> {
+        Ok(expr.name.clone())
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<String, ScriptError> {
+        self.parenthesize(&format!("= {}", expr.name), &[&expr.value])
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
+            Token::PlusPlus => "++",
+            Token::MinusMinus => "--",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        Ok(format!("({} {})", name, expr.name))
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<String, ScriptError> {
+        let mut expressions = vec![expr.callee.as_ref()];
+        expressions.extend(expr.arguments.iter());
+
+        self.parenthesize("call", &expressions)
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<String, ScriptError> {
+        let expressions: Vec<&Expression> = expr.elements.iter().collect();
+        self.parenthesize("array", &expressions)
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<String, ScriptError> {
+        self.parenthesize("index", &[&expr.target, &expr.index])
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<String, ScriptError> {
+        let parts: Vec<&Expression> = expr.parts.iter().collect();
+        self.parenthesize("interpolate", &parts)
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<String, ScriptError> {
+        let parts = [
+            self.nested(|printer| expr.object.accept(printer))?,
+            expr.name.clone(),
+        ];
+
+        Ok(self.join(".", &parts))
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<String, ScriptError> {
+        let mut parts = vec![];
+        for (key, value) in &expr.entries {
+            parts.push(self.nested(|printer| {
+                let key = key.accept(printer)?;
+                printer.parenthesize(&key, &[value])
+            })?);
+        }
+
+        Ok(self.join("map", &parts))
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<String, ScriptError> {
+        let parts = [
+            format!("({})", expr.params.join(" ")),
+            self.nested(|printer| expr.body.accept(printer))?,
+        ];
+
+        Ok(self.join("lambda", &parts))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<String, ScriptError> {
+        let mut parts = self.statements(&expr.statements)?;
+        if let Some(tail) = &expr.tail {
+            parts.push(self.nested(|printer| tail.accept(printer))?);
+        }
+
+        Ok(self.join("block", &parts))
+    }
+}
+
+impl StatementVisitor<String> for AstPrinter {
+    fn visit_expression_statement(&mut self, expression: &Expression) -> Result<String, ScriptError> {
+        self.parenthesize("expression", &[expression])
+    }
+
+    fn visit_print(&mut self, expressions: &[Expression]) -> Result<String, ScriptError> {
+        let expressions: Vec<&Expression> = expressions.iter().collect();
+        self.parenthesize("print", &expressions)
+    }
+
+    fn visit_assert(&mut self, condition: &Expression, message: Option<&Expression>, _position: Position) -> Result<String, ScriptError> {
+        let mut expressions = vec![condition];
+        expressions.extend(message);
+        self.parenthesize("assert", &expressions)
+    }
+
+    fn visit_include(&mut self, path: &str, _position: Position) -> Result<String, ScriptError> {
+        Ok(format!("(include \"{}\")", path))
+    }
+
+    fn visit_let(&mut self, name: &str, initializer: Option<&Expression>) -> Result<String, ScriptError> {
+        let initializer: Vec<&Expression> = initializer.into_iter().collect();
+        self.parenthesize(&format!("let {}", name), &initializer)
+    }
+
+    fn visit_if(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) -> Result<String, ScriptError> {
+        let mut parts = vec![
+            self.nested(|printer| condition.accept(printer))?,
+            self.nested(|printer| then_branch.accept(printer))?,
+        ];
+        if let Some(else_branch) = else_branch {
+            parts.push(self.nested(|printer| else_branch.accept(printer))?);
+        }
+
+        Ok(self.join("if", &parts))
+    }
+
+    fn visit_while(&mut self, condition: &Expression, body: &Statement, increment: Option<&Expression>) -> Result<String, ScriptError> {
+        let mut parts = vec![
+            self.nested(|printer| condition.accept(printer))?,
+            self.nested(|printer| body.accept(printer))?,
+        ];
+        if let Some(increment) = increment {
+            parts.push(self.nested(|printer| increment.accept(printer))?);
+        }
+
+        Ok(self.join("while", &parts))
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) -> Result<String, ScriptError> {
+        let parts = self.statements(statements)?;
+        Ok(self.join("block", &parts))
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<String, ScriptError> {
+        let mut parts = vec![format!("({})", params.join(" "))];
+        parts.extend(self.statements(body)?);
+
+        Ok(self.join(&format!("fn {}", name), &parts))
+    }
+
+    fn visit_return(&mut self, value: Option<&Expression>) -> Result<String, ScriptError> {
+        let value: Vec<&Expression> = value.into_iter().collect();
+        self.parenthesize("return", &value)
+    }
+
+    fn visit_break(&mut self) -> Result<String, ScriptError> {
+        Ok(String::from("(break)"))
+    }
+
+    fn visit_continue(&mut self) -> Result<String, ScriptError> {
+        Ok(String::from("(continue)"))
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::TokenMetadata;
+    use crate::{scanner::Scannable, parser::Parser};
 
     #[test]
     fn print() {
         let expr = Expression::Binary(BinaryExpression {
             left: Box::new(Expression::Unary(UnaryExpression {
-                operator: Token::Minus,
+                operator: TokenMetadata::synthetic(Token::Minus),
                 right: Box::new(Expression::Literal(LiteralExpression { 
                     literal: Token::Number(123f64) 
                 })),
             })),
-            operator: Token::Star,
+            operator: TokenMetadata::synthetic(Token::Star),
             right: Box::new(Expression::Grouping(GroupingExpression { 
                 group: Box::new(Expression::Literal(LiteralExpression { 
                     literal: Token::Number(45.67f64),
@@ -78,9 +325,65 @@ mod tests {
             })),
         });
     
-        let printer = AstPrinter;
+        let mut printer = AstPrinter::new();
         let exp = printer.print(expr).unwrap();
 
         assert_eq!(exp, "(* (- 123) (group 45.67))");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pretty() {
+        let expr = Parser::new("-123 * (45.67 + x)".tokens().unwrap()).parse().unwrap();
+
+        assert_eq!(AstPrinter::pretty().print(expr).unwrap(), [
+            "(*",
+            "  (-",
+            "    123)",
+            "  (group",
+            "    (+",
+            "      45.67",
+            "      x)))",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let tokens = "1 < 2 == 3 >= 4 != 5 / 6 > 7 <= 8".tokens().unwrap();
+        let expr = Parser::new(tokens).parse().unwrap();
+
+        let mut printer = AstPrinter::new();
+        let exp = printer.print(expr).unwrap();
+
+        assert_eq!(exp, "(!= (== (< 1 2) (>= 3 4)) (<= (> (/ 5 6) 7) 8))");
+    }
+
+    #[test]
+    fn keyword_literals() {
+        let mut printer = AstPrinter::new();
+        let mut print = |code: &str| printer.print(Parser::new(code.tokens().unwrap()).parse().unwrap()).unwrap();
+
+        assert_eq!(print("!true"), "(! true)");
+        assert_eq!(print("false"), "false");
+        assert_eq!(print("null"), "null");
+    }
+
+    #[test]
+    fn print_program() {
+        let code = "let x = 1; fn f(a, b) { return a; } if (x) print f(x, 2); else { x = 2; } for (;;) break;";
+        let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+
+        assert_eq!(AstPrinter::new().print_program(&program).unwrap(), [
+            "(let x 1)",
+            "(fn f (a b) (return a))",
+            "(if x (print (call f x 2)) (block (expression (= x 2))))",
+            "(while true (break))",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn print_statements() {
+        let program = Parser::new("let x = 1; if (x) print x;".tokens().unwrap()).parse_program().unwrap();
+
+        assert_eq!(AstPrinter::new().print_program(&program).unwrap(), "(let x 1)\n(if x (print x))");
+    }
+}
@@ -0,0 +1,208 @@
+use super::{types::*, escape};
+use crate::errors::ScriptError;
+use crate::token::Token;
+
+/// Prints expressions in reverse polish notation, operands first followed by
+/// their operator. Groupings are not needed in postfix form and flatten away
+pub struct RpnPrinter;
+
+impl RpnPrinter {
+    pub fn print(&mut self, expression: Expression) -> Result<String, ScriptError> {
+        expression.accept(self)
+    }
+
+    fn postfix(&mut self, operator: &str, expressions: &[&Expression]) -> Result<String, ScriptError> {
+        let mut out = String::new();
+        for expr in expressions {
+            out.push_str(&expr.accept(self)?);
+            out.push(' ');
+        }
+
+        out.push_str(operator);
+        Ok(out)
+    }
+}
+
+impl ExpressionVisitor<String> for RpnPrinter {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<String, ScriptError> {
+        // Negation gets its own name to tell it apart from subtraction
+        let name = match &expr.operator.token {
+            Token::Minus => "neg",
+            Token::Bang => "!",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        self.postfix(name, &[&expr.right])
+    }
+
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::StarStar => "**",
+            Token::EqualEqual => "==",
+            Token::BangEqual => "!=",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        self.postfix(name, &[&expr.left, &expr.right])
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
+            Token::And => "&&",
+            Token::Or => "||",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        self.postfix(name, &[&expr.left, &expr.right])
+    }
+
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<String, ScriptError> {
+        self.postfix("??", &[&expr.left, &expr.right])
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<String, ScriptError> {
+        expr.group.accept(self)
+    }
+
+    fn visit_literal(&mut self, expr: &LiteralExpression) -> Result<String, ScriptError> {
+        match &expr.literal {
+            Token::String(str) => Ok(format!("\"{}\"", escape(str))),
+            Token::Number(nbr) => Ok(nbr.to_string()),
+            Token::Integer(nbr) => Ok(nbr.to_string()),
+            Token::True => Ok(String::from("true")),
+            Token::False => Ok(String::from("false")),
+            Token::Null => Ok(String::from("null")),
+            _ => Err(ScriptError::AstPrinterError),
+        }
+    }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<String, ScriptError> {
+        Ok(expr.name.clone())
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<String, ScriptError> {
+        Ok(format!("{} {} =", expr.value.accept(self)?, expr.name))
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<String, ScriptError> {
+        let name = match &expr.operator.token {
+            Token::PlusPlus => "++",
+            Token::MinusMinus => "--",
+            _ => return Err(ScriptError::AstPrinterError),
+        };
+
+        Ok(format!("{} {}", expr.name, name))
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<String, ScriptError> {
+        // The argument count is part of the operator, otherwise the call is ambiguous
+        let mut expressions: Vec<&Expression> = expr.arguments.iter().collect();
+        expressions.push(&expr.callee);
+
+        self.postfix(&format!("call/{}", expr.arguments.len()), &expressions)
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<String, ScriptError> {
+        // Like calls, the element count is part of the operator
+        let expressions: Vec<&Expression> = expr.elements.iter().collect();
+        self.postfix(&format!("array/{}", expr.elements.len()), &expressions)
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<String, ScriptError> {
+        let parts: Vec<&Expression> = expr.parts.iter().collect();
+        self.postfix(&format!("interpolate/{}", expr.parts.len()), &parts)
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<String, ScriptError> {
+        self.postfix("index", &[&expr.target, &expr.index])
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<String, ScriptError> {
+        Ok(format!("{} {} .", expr.object.accept(self)?, expr.name))
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<String, ScriptError> {
+        // Each key is pushed right before its value
+        let mut out = String::new();
+        for (key, value) in &expr.entries {
+            out.push_str(&format!("{} {} ", key.accept(self)?, value.accept(self)?));
+        }
+
+        out.push_str(&format!("map/{}", expr.entries.len()));
+        Ok(out)
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<String, ScriptError> {
+        // The body isn't evaluated where it stands, so it is kept apart
+        Ok(format!("({}) [{}] lambda", expr.params.join(" "), expr.body.accept(self)?))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<String, ScriptError> {
+        // Statements have no postfix form, only the value of a block can be printed
+        match (&expr.statements[..], &expr.tail) {
+            ([], Some(tail)) => tail.accept(self),
+            _ => Err(ScriptError::AstPrinterError),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenMetadata;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    fn parse(code: &str) -> Expression {
+        let tokens = code.tokens().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn print() {
+        let expr = Expression::Binary(BinaryExpression {
+            left: Box::new(Expression::Unary(UnaryExpression {
+                operator: TokenMetadata::synthetic(Token::Minus),
+                right: Box::new(Expression::Literal(LiteralExpression { 
+                    literal: Token::Number(123f64) 
+                })),
+            })),
+            operator: TokenMetadata::synthetic(Token::Star),
+            right: Box::new(Expression::Grouping(GroupingExpression { 
+                group: Box::new(Expression::Literal(LiteralExpression { 
+                    literal: Token::Number(45.67f64),
+                })),
+            })),
+        });
+
+        let mut printer = RpnPrinter;
+        let exp = printer.print(expr).unwrap();
+
+        assert_eq!(exp, "123 neg 45.67 *");
+    }
+
+    #[test]
+    fn groupings_flatten() {
+        let mut printer = RpnPrinter;
+
+        assert_eq!(printer.print(parse("(1 + 2) * 3")).unwrap(), "1 2 + 3 *");
+        assert_eq!(printer.print(parse("1 + 2 * 3")).unwrap(), "1 2 3 * +");
+        assert_eq!(printer.print(parse("x = f(1, !a)")).unwrap(), "1 a ! f call/2 x =");
+    }
+
+    #[test]
+    fn string_escapes() {
+        let mut printer = RpnPrinter;
+
+        assert_eq!(printer.print(parse(r#""a\"b" + "a\nb""#)).unwrap(), r#""a\"b" "a\nb" +"#);
+    }
+}
@@ -0,0 +1,57 @@
+use super::types::Expression;
+use crate::{errors::ScriptError, scanner::Position};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Expression(Expression),
+    /// Prints its values separated by spaces, an empty print only a newline
+    Print(Vec<Expression>),
+    /// The position is where the condition starts, reported when it fails
+    Assert { condition: Expression, message: Option<Expression>, position: Position },
+    /// The position is where the path is, reported when it can't be included
+    Include { path: String, position: Position },
+    Let { name: String, initializer: Option<Expression> },
+    If { condition: Expression, then_branch: Box<Statement>, else_branch: Option<Box<Statement>> },
+    /// The increment of a desugared for loop runs after the body, also when
+    /// the body continues
+    While { condition: Expression, body: Box<Statement>, increment: Option<Expression> },
+    Block(Vec<Statement>),
+    Function { name: String, params: Vec<String>, body: Vec<Statement> },
+    Return(Option<Expression>),
+    Break,
+    Continue,
+}
+
+impl Statement {
+    pub fn accept<T>(&self, visitor: &mut dyn StatementVisitor<T>) -> Result<T, ScriptError> {
+        match self {
+            Self::Expression(expression) => visitor.visit_expression_statement(expression),
+            Self::Print(expressions) => visitor.visit_print(expressions),
+            Self::Assert { condition, message, position } => visitor.visit_assert(condition, message.as_ref(), *position),
+            Self::Include { path, position } => visitor.visit_include(path, *position),
+            Self::Let { name, initializer } => visitor.visit_let(name, initializer.as_ref()),
+            Self::If { condition, then_branch, else_branch } => visitor.visit_if(condition, then_branch, else_branch.as_deref()),
+            Self::While { condition, body, increment } => visitor.visit_while(condition, body, increment.as_ref()),
+            Self::Block(statements) => visitor.visit_block(statements),
+            Self::Function { name, params, body } => visitor.visit_function(name, params, body),
+            Self::Return(value) => visitor.visit_return(value.as_ref()),
+            Self::Break => visitor.visit_break(),
+            Self::Continue => visitor.visit_continue(),
+        }
+    }
+}
+
+pub trait StatementVisitor<T> {
+    fn visit_expression_statement(&mut self, expression: &Expression) -> Result<T, ScriptError>;
+    fn visit_print(&mut self, expressions: &[Expression]) -> Result<T, ScriptError>;
+    fn visit_assert(&mut self, condition: &Expression, message: Option<&Expression>, position: Position) -> Result<T, ScriptError>;
+    fn visit_include(&mut self, path: &str, position: Position) -> Result<T, ScriptError>;
+    fn visit_let(&mut self, name: &str, initializer: Option<&Expression>) -> Result<T, ScriptError>;
+    fn visit_if(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) -> Result<T, ScriptError>;
+    fn visit_while(&mut self, condition: &Expression, body: &Statement, increment: Option<&Expression>) -> Result<T, ScriptError>;
+    fn visit_block(&mut self, statements: &[Statement]) -> Result<T, ScriptError>;
+    fn visit_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<T, ScriptError>;
+    fn visit_return(&mut self, value: Option<&Expression>) -> Result<T, ScriptError>;
+    fn visit_break(&mut self) -> Result<T, ScriptError>;
+    fn visit_continue(&mut self) -> Result<T, ScriptError>;
+}
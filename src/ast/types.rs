@@ -1,9 +1,11 @@
-use crate::token::Token;
+use crate::token::{Token, TokenMetadata};
+use crate::scanner::Position;
 use crate::errors::ScriptError;
+use super::statements::Statement;
 
 macro_rules! define_ast_types {
     ($($enum:ident, $name:ident, $visit:ident { $($prop_name:ident: $prop_type:ty),* },)*) => {
-        #[derive(Debug, PartialEq)]
+        #[derive(Debug, PartialEq, Clone)]
         pub enum Expression {
             $(
                 $enum($name),
@@ -11,7 +13,7 @@ macro_rules! define_ast_types {
         }
 
         impl Expression {
-            pub fn accept<T>(&self, visitor: &dyn ExpressionVisitor<T>) -> Result<T, ScriptError> {
+            pub fn accept<T>(&self, visitor: &mut dyn ExpressionVisitor<T>) -> Result<T, ScriptError> {
                 match self {
                     $(
                         Self::$enum(e) => e.accept(visitor),
@@ -22,12 +24,12 @@ macro_rules! define_ast_types {
 
         pub trait ExpressionVisitor<T> {
             $(
-                fn $visit(&self, expression: &$name) -> Result<T, ScriptError>;
+                fn $visit(&mut self, expression: &$name) -> Result<T, ScriptError>;
             )*
         }
 
         $(
-            #[derive(Debug, PartialEq)]
+            #[derive(Debug, PartialEq, Clone)]
             pub struct $name {
                 $(
                     pub $prop_name: $prop_type,
@@ -35,7 +37,7 @@ macro_rules! define_ast_types {
             }
 
             impl $name {
-                fn accept<T>(&self, visitor: &dyn ExpressionVisitor<T>) -> Result<T, ScriptError> {
+                fn accept<T>(&self, visitor: &mut dyn ExpressionVisitor<T>) -> Result<T, ScriptError> {
                     visitor.$visit(self)
                 }
             }
@@ -44,8 +46,37 @@ macro_rules! define_ast_types {
 }
 
 define_ast_types!(
-    Unary, UnaryExpression, visit_unary {operator: Token, right: Box<Expression>},
-    Binary, BinaryExpression, visit_binary {left: Box<Expression>, operator: Token, right: Box<Expression>},
+    Unary, UnaryExpression, visit_unary {operator: TokenMetadata, right: Box<Expression>},
+    Binary, BinaryExpression, visit_binary {left: Box<Expression>, operator: TokenMetadata, right: Box<Expression>},
+    Logical, LogicalExpression, visit_logical {left: Box<Expression>, operator: TokenMetadata, right: Box<Expression>},
+    Coalesce, CoalesceExpression, visit_coalesce {left: Box<Expression>, operator: TokenMetadata, right: Box<Expression>},
     Grouping, GroupingExpression, visit_grouping {group: Box<Expression>},
     Literal, LiteralExpression, visit_literal {literal: Token},
-);
\ No newline at end of file
+    Variable, VariableExpression, visit_variable {name: String, position: Position},
+    Assign, AssignExpression, visit_assign {name: String, value: Box<Expression>, position: Position},
+    Increment, IncrementExpression, visit_increment {name: String, operator: TokenMetadata},
+    Call, CallExpression, visit_call {callee: Box<Expression>, arguments: Vec<Expression>},
+    Array, ArrayExpression, visit_array {elements: Vec<Expression>},
+    Index, IndexExpression, visit_index {target: Box<Expression>, index: Box<Expression>},
+    Get, GetExpression, visit_get {object: Box<Expression>, name: String},
+    Map, MapExpression, visit_map {entries: Vec<(Expression, Expression)>},
+    Lambda, LambdaExpression, visit_lambda {params: Vec<String>, body: Box<Expression>},
+    Block, BlockExpression, visit_block_expression {statements: Vec<Statement>, tail: Option<Box<Expression>>},
+    Interpolation, InterpolationExpression, visit_interpolation {parts: Vec<Expression>},
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    #[test]
+    fn clone() {
+        let Expression::Binary(binary) = Parser::new("a * (1 + f(2))".tokens().unwrap()).parse().unwrap() else {
+            panic!("Expected a binary expression")
+        };
+
+        assert_eq!(binary.clone(), binary);
+        assert_eq!(Expression::Binary(binary.clone()), Expression::Binary(binary));
+    }
+}
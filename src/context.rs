@@ -1,9 +1,41 @@
+use std::io::Write;
+use crate::interpreter::Interpreter;
+
 pub struct Context {
     pub should_exit: bool,
+
+    /// Status code the process exits with
+    pub exit_code: i32,
+
+    /// Upper limit of expression nodes in a parsed script, if any
+    pub max_nodes: Option<usize>,
+
+    /// Name of where the scripts are read from, like a file name, shown in
+    /// the positions of errors
+    pub source: Option<String>,
+
+    /// Print the value of a last expression without a semicolon, like the
+    /// REPL does
+    pub repl_echo: bool,
+
+    /// Interpreter executing every script run in this context, its globals
+    /// persist between runs, e.g. lines in the REPL
+    pub interpreter: Interpreter,
 }
 
 impl Context {
     pub fn new() -> Self {
-        Self { should_exit: false }
+        Self { should_exit: false, exit_code: 0, max_nodes: None, source: None, repl_echo: false, interpreter: Interpreter::new() }
+    }
+
+    /// Create a context printing to any writer instead of stdout
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Self { should_exit: false, exit_code: 0, max_nodes: None, source: None, repl_echo: false, interpreter: Interpreter::with_output(output) }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,117 @@
+use crate::{ast::quote, errors::{ParserError, ScannerError, Warning}, parser::Parser, scanner::{Position, Scannable}};
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a script, as structured data for editors and other
+/// tools rather than a message to show a user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+
+    /// Line and column where the problem is, both zero when unknown
+    pub line: usize,
+    pub column: usize,
+
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    fn new(message: String, position: Position, severity: Severity) -> Self {
+        Diagnostic { message, line: position.line(), column: position.column(), severity }
+    }
+
+    /// The diagnostic as a single line JSON object
+    pub fn to_json(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        format!(
+            r#"{{"message":{},"line":{},"column":{},"severity":"{}"}}"#,
+            quote(&self.message), self.line, self.column, severity
+        )
+    }
+}
+
+impl From<&ScannerError> for Diagnostic {
+    fn from(err: &ScannerError) -> Self {
+        Diagnostic::new(err.to_string(), err.position(), Severity::Error)
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(err: &ParserError) -> Self {
+        Diagnostic::new(err.to_string(), err.position(), Severity::Error)
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Diagnostic::new(warning.to_string(), warning.position(), Severity::Warning)
+    }
+}
+
+/// Scan and parse a script, reporting every error and warning found rather
+/// than stopping at the first one. Tokens that did scan are still parsed
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    let (tokens, scanner_errors) = source.tokens_partial();
+    let mut diagnostics: Vec<Diagnostic> = scanner_errors.iter().map(Diagnostic::from).collect();
+
+    let mut parser = Parser::new(tokens);
+    if let Err(errors) = parser.parse_program() {
+        diagnostics.extend(errors.iter().map(Diagnostic::from));
+    }
+    diagnostics.extend(parser.warnings.iter().map(Diagnostic::from));
+
+    diagnostics
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanner_and_parser_errors() {
+        // The stray character is skipped, so the rest of the line still parses
+        let diagnostics = diagnostics("let x = 1 @;\nprint (1 + 2;");
+
+        assert_eq!(diagnostics, vec![
+            Diagnostic { message: String::from("Unexpected token at 1:11"), line: 1, column: 11, severity: Severity::Error },
+            Diagnostic { message: String::from("Expected ')' but found ';' at 2:13"), line: 2, column: 13, severity: Severity::Error },
+        ]);
+    }
+
+    #[test]
+    fn parser_error_positions() {
+        let diagnostics = diagnostics("let x = 1;\nprint x");
+        assert_eq!(diagnostics, vec![
+            Diagnostic { message: String::from("Expected ';' after statement at 2:8"), line: 2, column: 8, severity: Severity::Error },
+        ]);
+
+        let code = format!("print {}1{};", "(".repeat(100), ")".repeat(100));
+        let nested = super::diagnostics(&code);
+        assert_eq!((nested[0].line, nested[0].column), (1, 71));
+    }
+
+    #[test]
+    fn warnings() {
+        let diagnostics = diagnostics("let x = 1;\nif (x = 2) print x;");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 5));
+    }
+
+    #[test]
+    fn json() {
+        let diagnostic = Diagnostic { message: String::from("Expected \"x\""), line: 0, column: 0, severity: Severity::Error };
+        assert_eq!(diagnostic.to_json(), r#"{"message":"Expected \"x\"","line":0,"column":0,"severity":"error"}"#);
+    }
+}
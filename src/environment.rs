@@ -0,0 +1,113 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use crate::{errors::RuntimeError, value::Value};
+
+/// Storage of the variables visible to the running script. Scopes are
+/// chained, lookups that miss locally continue in the enclosing scope
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new inner scope of an enclosing environment
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment { values: HashMap::new(), enclosing: Some(enclosing) }
+    }
+
+    /// Define a new variable in this scope, or redefine an existing one
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.values.insert(String::from(name), value);
+    }
+
+    /// Get the value of a variable from the innermost scope defining it
+    pub fn get(&self, name: &str) -> Result<Value, RuntimeError> {
+        match (self.values.get(name), &self.enclosing) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(RuntimeError::UndefinedVariable(String::from(name))),
+        }
+    }
+
+    /// Assign a new value to an already defined variable, in the innermost
+    /// scope defining it
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        match (self.values.get_mut(name), &self.enclosing) {
+            (Some(slot), _) => {
+                *slot = value;
+                Ok(())
+            },
+            (None, Some(enclosing)) => enclosing.borrow_mut().assign(name, value),
+            (None, None) => Err(RuntimeError::UndefinedVariable(String::from(name))),
+        }
+    }
+
+    /// Variables defined in this scope, not the enclosing ones, ordered by name
+    pub fn bindings(&self) -> Vec<(&str, &Value)> {
+        let mut bindings: Vec<(&str, &Value)> = self.values.iter().map(|(name, value)| (name.as_str(), value)).collect();
+        bindings.sort_by_key(|(name, _)| *name);
+        bindings
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn define_and_get() {
+        let mut environment = Environment::new();
+        environment.define("x", Value::Number(1f64));
+
+        assert_eq!(environment.get("x").unwrap(), Value::Number(1f64));
+
+        environment.assign("x", Value::Bool(true)).unwrap();
+        assert_eq!(environment.get("x").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn enclosing_scope() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().define("x", Value::Number(1f64));
+        outer.borrow_mut().define("y", Value::Number(2f64));
+
+        let mut inner = Environment::with_enclosing(outer.clone());
+        inner.define("x", Value::Number(10f64));
+        inner.assign("y", Value::Number(20f64)).unwrap();
+
+        assert_eq!(inner.get("x").unwrap(), Value::Number(10f64));
+        assert_eq!(outer.borrow().get("x").unwrap(), Value::Number(1f64));
+        assert_eq!(outer.borrow().get("y").unwrap(), Value::Number(20f64));
+    }
+
+    #[test]
+    fn bindings() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().define("z", Value::Null);
+
+        let mut inner = Environment::with_enclosing(outer);
+        inner.define("b", Value::Number(2f64));
+        inner.define("a", Value::Number(1f64));
+
+        assert_eq!(inner.bindings(), vec![("a", &Value::Number(1f64)), ("b", &Value::Number(2f64))]);
+    }
+
+    #[test]
+    fn get_undefined() {
+        let environment = Environment::new();
+
+        assert!(matches!(environment.get("x"), Err(RuntimeError::UndefinedVariable(name)) if name == "x"));
+    }
+
+    #[test]
+    fn assign_undefined() {
+        let mut environment = Environment::new();
+
+        assert!(matches!(environment.assign("x", Value::Null), Err(RuntimeError::UndefinedVariable(name)) if name == "x"));
+    }
+}
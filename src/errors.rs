@@ -1,11 +1,23 @@
 use std::{error::Error, fmt::Display};
 use crate::scanner::Position;
+use crate::token::Token;
+use crate::value::Value;
 
 #[derive(Debug)]
 pub enum ScriptError {
     FileIo(std::io::Error),
     ScannerError(ScannerError),
     ParserError(ParserError),
+    ParserErrors(Vec<ParserError>),
+    RuntimeError(RuntimeError),
+    /// Not an error, unwinds the value of a return statement to its call
+    Return(Value),
+    /// Not an error, unwinds out of the body of the innermost loop
+    Break,
+    /// Not an error, unwinds to the next iteration of the innermost loop
+    Continue,
+    /// Not an error, unwinds all the way out of a script calling exit
+    Exit(i32),
     AstPrinterError,
 }
 
@@ -13,13 +25,75 @@ impl Display for ScriptError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::FileIo(err) => err.fmt(f),
-            Self::ScannerError(err) => err.fmt(f),
-            Self::ParserError(err) => err.fmt(f),
+            Self::ScannerError(err) => write!(f, "error[{}]: {}", err.code(), err),
+            Self::ParserError(err) => write!(f, "error[{}]: {}", err.code(), err),
+            Self::ParserErrors(errors) => {
+                for (index, err) in errors.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "error[{}]: {}", err.code(), err)?;
+                }
+                Ok(())
+            },
+            Self::RuntimeError(err) => write!(f, "error[{}]: {}", err.code(), err),
+            Self::Return(value) => write!(f, "Returned {}", value),
+            Self::Break => write!(f, "Broke out of loop"),
+            Self::Continue => write!(f, "Continued loop"),
+            Self::Exit(code) => write!(f, "Exited with code {}", code),
             Self::AstPrinterError => write!(f, "Error printing AST"),
         }
     }
 }
 
+impl ScriptError {
+    /// Format the error like Display does, followed by the offending line of
+    /// the source with a caret under the reported column, where known. The
+    /// name is the one the source was scanned with, if any, see
+    /// [crate::scanner::TokenStream::named]
+    pub fn diagnostic(&self, source: &str, name: Option<&str>) -> String {
+        // Errors in an included file are at lines of that file, which isn't
+        // the source at hand
+        let render = |message: String, position: Option<Position>| match position {
+            Some(position) if position.source() == name => format!("{}\n{}", message, snippet(source, position)),
+            _ => message,
+        };
+
+        match self {
+            Self::ScannerError(err) => render(self.to_string(), Some(err.position())),
+            Self::ParserError(err) => render(self.to_string(), Some(err.position())),
+            Self::ParserErrors(errors) => errors
+                .iter()
+                .map(|err| render(format!("error[{}]: {}", err.code(), err), Some(err.position())))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::RuntimeError(err) => render(self.to_string(), err.position()),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Whether the script failed to parse only because it ended early, see
+    /// ParserError::is_incomplete
+    pub fn is_incomplete(&self) -> bool {
+        match self {
+            Self::ParserError(err) => err.is_incomplete(),
+            Self::ParserErrors(errors) => !errors.is_empty() && errors.iter().all(ParserError::is_incomplete),
+            _ => false,
+        }
+    }
+
+    /// Status code to exit the process with on this error, following the
+    /// sysexits convention: 65 for bad scripts and 70 for failures running them
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::ScannerError(_) | Self::ParserError(_) | Self::ParserErrors(_) => 65,
+            Self::FileIo(_) => 74,
+            Self::Exit(code) => *code,
+            Self::RuntimeError(_) | Self::Return(_) | Self::Break | Self::Continue | Self::AstPrinterError => 70,
+        }
+    }
+}
+
 impl Error for ScriptError {}
 
 impl From<std::io::Error> for ScriptError {
@@ -40,17 +114,36 @@ impl From<ParserError> for ScriptError {
     }
 }
 
+impl From<Vec<ParserError>> for ScriptError {
+    fn from(value: Vec<ParserError>) -> Self {
+        Self::ParserErrors(value)
+    }
+}
 
+impl From<RuntimeError> for ScriptError {
+    fn from(value: RuntimeError) -> Self {
+        Self::RuntimeError(value)
+    }
+}
 
 
 
 
 
-#[derive(Debug)]
+
+
+#[derive(Debug, PartialEq)]
 pub enum ScannerError {
     UnexpectedToken(Position),
     NumberLiteralParsingError(Position),
     UnterminatedMultilineComment(Position),
+    UnterminatedString(Position),
+    NewlineInString(Position),
+    InvalidEscapeSequence(Position),
+    InvalidCharLiteral(Position),
+    InvalidUnicodeEscape(Position),
+    UnterminatedInterpolation(Position),
+    InterpolationTooDeep(Position),
 }
 
 impl Display for ScannerError {
@@ -59,26 +152,777 @@ impl Display for ScannerError {
             Self::UnexpectedToken(position) => write!(f, "Unexpected token at {}", position),
             Self::NumberLiteralParsingError(position) => write!(f, "Error parsing number at {}", position),
             Self::UnterminatedMultilineComment(position) => write!(f, "Unterminated multiline comment at {}", position),
+            Self::UnterminatedString(position) => write!(f, "Unterminated string at {}", position),
+            Self::NewlineInString(position) => write!(f, "Newline in string at {}", position),
+            Self::InvalidEscapeSequence(position) => write!(f, "Invalid escape sequence at {}", position),
+            Self::InvalidCharLiteral(position) => write!(f, "Invalid character literal at {}", position),
+            Self::InvalidUnicodeEscape(position) => write!(f, "Invalid unicode escape at {}", position),
+            Self::UnterminatedInterpolation(position) => write!(f, "Unterminated interpolation at {}", position),
+            Self::InterpolationTooDeep(position) => write!(f, "Interpolations are nested too deeply at {}", position),
         }
     }
 }
 
+impl ScannerError {
+    /// Short code identifying the kind of error, see `lhscript --explain <code>`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedToken(_) => "E0001",
+            Self::NumberLiteralParsingError(_) => "E0002",
+            Self::UnterminatedMultilineComment(_) => "E0003",
+            Self::UnterminatedString(_) => "E0004",
+            Self::NewlineInString(_) => "E0005",
+            Self::InvalidEscapeSequence(_) => "E0006",
+            Self::InvalidCharLiteral(_) => "E0007",
+            Self::InvalidUnicodeEscape(_) => "E0008",
+            Self::UnterminatedInterpolation(_) => "E0009",
+            Self::InterpolationTooDeep(_) => "E0010",
+        }
+    }
 
-
-
+    /// Where in the code the error was found
+    pub fn position(&self) -> Position {
+        match self {
+            Self::UnexpectedToken(position)
+            | Self::NumberLiteralParsingError(position)
+            | Self::UnterminatedMultilineComment(position)
+            | Self::UnterminatedString(position)
+            | Self::NewlineInString(position)
+            | Self::InvalidEscapeSequence(position)
+            | Self::InvalidCharLiteral(position)
+            | Self::InvalidUnicodeEscape(position)
+            | Self::UnterminatedInterpolation(position)
+            | Self::InterpolationTooDeep(position) => *position,
+        }
+    }
+}
 
 impl Error for ScannerError {}
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ParserError {
-    Unexpected,
-    Consume
+    Unexpected { found: Token, position: Position, suggestion: Option<String> },
+    Consume { expected: Token, found: Token, position: Position, suggestion: Option<String> },
+    ExpectedSemicolon(Position),
+    ExpectedIdentifier(Position),
+    ExpectedLeftParenthesis(&'static str, Position),
+    ExpectedRightParenthesis(&'static str, Position),
+    InvalidAssignmentTarget(Position),
+    UnterminatedBlock(Position),
+    TooManyParameters(Position),
+    TooManyNodes(usize, Position),
+    UnexpectedEof(Position),
+    ExpectedKey(Position),
+    TooDeep(usize, Position),
+    UndeclaredVariable { name: String, position: Position },
+    ReadInOwnInitializer { name: String, position: Position },
+    ExpectedIncludePath(Position),
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Placeholder")
+        match self {
+            Self::Unexpected { found, position, suggestion } => write!(f, "Unexpected {} at {}{}", describe(found), position, hint(suggestion)),
+            Self::Consume { expected, found, position, suggestion } => write!(f, "Expected {} but found {} at {}{}", describe(expected), describe(found), position, hint(suggestion)),
+            Self::ExpectedSemicolon(position) => write!(f, "Expected ';' after statement at {}", position),
+            Self::ExpectedIdentifier(position) => write!(f, "Expected an identifier at {}", position),
+            Self::ExpectedLeftParenthesis(keyword, position) => write!(f, "Expected '(' after '{}' at {}", keyword, position),
+            Self::ExpectedRightParenthesis(keyword, position) => write!(f, "Expected ')' after '{}' condition at {}", keyword, position),
+            Self::InvalidAssignmentTarget(position) => write!(f, "Invalid assignment target at {}", position),
+            Self::UnterminatedBlock(position) => write!(f, "Expected '}}' after block at {}", position),
+            Self::TooManyParameters(position) => write!(f, "Can't have more than 255 parameters at {}", position),
+            Self::TooManyNodes(max, position) => write!(f, "Script is too large, more than {} expression nodes at {}", max, position),
+            Self::UnexpectedEof(position) => write!(f, "Unexpected end of file at {}", position),
+            Self::ExpectedKey(position) => write!(f, "Expected a string, number or boolean as map key at {}", position),
+            Self::TooDeep(max, position) => write!(f, "Script is nested too deeply, more than {} levels at {}", max, position),
+            Self::UndeclaredVariable { name, position } => write!(f, "Variable '{}' is used but never declared at {}", name, position),
+            Self::ReadInOwnInitializer { name, position } => write!(f, "Variable '{}' is read in its own initializer at {}", name, position),
+            Self::ExpectedIncludePath(position) => write!(f, "Expected a string with the path of the file to include at {}", position),
+        }
+    }
+}
+
+impl ParserError {
+    /// Short code identifying the kind of error, see `lhscript --explain <code>`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unexpected { .. } => "E0101",
+            Self::Consume { .. } => "E0102",
+            Self::ExpectedSemicolon(_) => "E0103",
+            Self::ExpectedIdentifier(_) => "E0104",
+            Self::ExpectedLeftParenthesis(..) => "E0105",
+            Self::ExpectedRightParenthesis(..) => "E0106",
+            Self::InvalidAssignmentTarget(_) => "E0107",
+            Self::UnterminatedBlock(_) => "E0108",
+            Self::TooManyParameters(_) => "E0109",
+            Self::TooManyNodes(..) => "E0110",
+            Self::UnexpectedEof(_) => "E0111",
+            Self::ExpectedKey(_) => "E0112",
+            Self::TooDeep(..) => "E0113",
+            Self::UndeclaredVariable { .. } => "E0114",
+            Self::ReadInOwnInitializer { .. } => "E0115",
+            Self::ExpectedIncludePath(_) => "E0116",
+        }
+    }
+
+    /// Where in the code the error was found
+    pub fn position(&self) -> Position {
+        match self {
+            Self::Unexpected { position, .. }
+            | Self::Consume { position, .. }
+            | Self::ExpectedSemicolon(position)
+            | Self::ExpectedIdentifier(position)
+            | Self::ExpectedLeftParenthesis(_, position)
+            | Self::ExpectedRightParenthesis(_, position)
+            | Self::InvalidAssignmentTarget(position)
+            | Self::UnterminatedBlock(position)
+            | Self::TooManyParameters(position)
+            | Self::TooManyNodes(_, position)
+            | Self::UnexpectedEof(position)
+            | Self::ExpectedKey(position)
+            | Self::TooDeep(_, position)
+            | Self::UndeclaredVariable { position, .. }
+            | Self::ReadInOwnInitializer { position, .. }
+            | Self::ExpectedIncludePath(position) => *position,
+        }
+    }
+
+    /// Whether the code ended in the middle of a statement, so more code
+    /// could complete it rather than the code being invalid
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, Self::UnexpectedEof(_) | Self::UnterminatedBlock(_) | Self::Consume { found: Token::Eof, .. })
+    }
+}
+
+impl Error for ParserError {}
+
+#[derive(Debug)]
+pub enum RuntimeError {
+    OperandMustBeNumber(Position),
+    OperandsMustBeNumbersOrStrings(Position),
+    DivisionByZero(Position),
+    UndefinedVariable(String),
+    NotCallable,
+    ArityMismatch { expected: usize, found: usize },
+    ReturnOutsideFunction,
+    InvalidArgument(&'static str),
+    IndexOutOfBounds { index: i64, length: usize },
+    InvalidIndex,
+    NotIndexable,
+    OutsideLoop(&'static str),
+    MissingKey(String),
+    InvalidKey,
+    IntegerOverflow(Position),
+    AssertionFailed { message: Option<String>, position: Position },
+    ExecutionLimitExceeded(usize),
+    CircularInclude { path: String, position: Position },
+    StackOverflow(usize),
+    Unsupported(&'static str),
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OperandMustBeNumber(position) => write!(f, "Operands must be numbers at {}", position),
+            Self::OperandsMustBeNumbersOrStrings(position) => write!(f, "Operands must be two numbers or two strings at {}", position),
+            Self::DivisionByZero(position) => write!(f, "Division by zero at {}", position),
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable '{}'", name),
+            Self::NotCallable => write!(f, "Can only call functions"),
+            Self::ArityMismatch { expected, found } => write!(f, "Expected {} arguments but got {}", expected, found),
+            Self::ReturnOutsideFunction => write!(f, "Can't return from top-level code"),
+            Self::InvalidArgument(reason) => write!(f, "Invalid argument, {}", reason),
+            Self::IndexOutOfBounds { index, length } => write!(f, "Index {} is out of bounds for an array of length {}", index, length),
+            Self::InvalidIndex => write!(f, "Array indices must be whole numbers"),
+            Self::NotIndexable => write!(f, "Can only index arrays and maps"),
+            Self::OutsideLoop(keyword) => write!(f, "Can't use '{}' outside of a loop", keyword),
+            Self::MissingKey(key) => write!(f, "Key '{}' not found, reading a key missing from a map is an error", key),
+            Self::InvalidKey => write!(f, "Map keys must be strings, whole numbers or booleans"),
+            Self::IntegerOverflow(position) => write!(f, "Integer overflow at {}", position),
+            Self::AssertionFailed { message: Some(message), position } => write!(f, "Assertion failed at {}: {}", position, message),
+            Self::AssertionFailed { message: None, position } => write!(f, "Assertion failed at {}", position),
+            Self::ExecutionLimitExceeded(max) => write!(f, "Execution limit exceeded, more than {} loop iterations", max),
+            Self::CircularInclude { path, position } => write!(f, "File '{}' includes itself at {}", path, position),
+            Self::StackOverflow(max) => write!(f, "Stack overflow, more than {} nested function calls", max),
+            Self::Unsupported(what) => write!(f, "Evaluating {} is not supported yet", what),
+        }
+    }
+}
+
+impl RuntimeError {
+    /// Short code identifying the kind of error, see `lhscript --explain <code>`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::OperandMustBeNumber(_) => "E0201",
+            Self::Unsupported(_) => "E0202",
+            Self::OperandsMustBeNumbersOrStrings(_) => "E0203",
+            Self::DivisionByZero(_) => "E0204",
+            Self::UndefinedVariable(_) => "E0205",
+            Self::NotCallable => "E0206",
+            Self::ArityMismatch { .. } => "E0207",
+            Self::ReturnOutsideFunction => "E0208",
+            Self::InvalidArgument(_) => "E0209",
+            Self::IndexOutOfBounds { .. } => "E0210",
+            Self::InvalidIndex => "E0211",
+            Self::NotIndexable => "E0212",
+            Self::OutsideLoop(_) => "E0213",
+            Self::MissingKey(_) => "E0214",
+            Self::InvalidKey => "E0215",
+            Self::IntegerOverflow(_) => "E0216",
+            Self::AssertionFailed { .. } => "E0217",
+            Self::ExecutionLimitExceeded(_) => "E0218",
+            Self::CircularInclude { .. } => "E0219",
+            Self::StackOverflow(_) => "E0220",
+        }
+    }
+
+    /// Where in the code the error was found, if known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::OperandMustBeNumber(position)
+            | Self::OperandsMustBeNumbersOrStrings(position)
+            | Self::DivisionByZero(position)
+            | Self::IntegerOverflow(position)
+            | Self::AssertionFailed { position, .. }
+            | Self::CircularInclude { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+}
+
+impl Error for RuntimeError {}
+
+/// Render the line of code at a position with a caret under its column
+///
+/// ```text
+/// 2 | let @ = 1;
+///   |     ^
+/// ```
+pub fn snippet(source: &str, position: Position) -> String {
+    let line = source.lines().nth(position.line().saturating_sub(1)).unwrap_or("");
+    let gutter = position.line().to_string();
+
+    // Keep tabs in the indentation, so the caret lines up with the code
+    let indent: String = line
+        .chars()
+        .take(position.column().saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!("{} | {}\n{} | {}^", gutter, line, " ".repeat(gutter.len()), indent)
+}
+
+/// Describe a token the way it appears in the code, for error messages
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Identifier(_) => format!("identifier '{}'", token),
+        Token::String(_) | Token::TemplateString(_) => format!("string {}", token),
+        Token::Char(_) => format!("character {}", token),
+        Token::Number(_) | Token::Integer(_) => format!("number {}", token),
+        Token::LineComment(_) | Token::BlockComment(_) => String::from("comment"),
+        Token::Whitespace | Token::Eof => token.to_string(),
+        _ => format!("'{}'", token),
+    }
+}
+
+/// A hint naming the keyword a misspelled identifier was likely meant to
+/// be, for error messages, or nothing
+fn hint(suggestion: &Option<String>) -> String {
+    suggestion.as_ref()
+        .map(|keyword| format!(", did you mean '{}'?", keyword))
+        .unwrap_or_default()
+}
+
+/// Suspicious but valid code, reported without stopping the parser
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    AssignmentInCondition(Position),
+    UnreachableCode(Position),
+}
+
+impl Warning {
+    /// Where in the code the suspicious code is
+    pub fn position(&self) -> Position {
+        match self {
+            Self::AssignmentInCondition(position) | Self::UnreachableCode(position) => *position,
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AssignmentInCondition(position) => write!(f, "Assignment used as condition at {}, did you mean '=='?", position),
+            Self::UnreachableCode(position) => write!(f, "Unreachable code at {}, it follows a return", position),
+        }
     }
 }
 
-impl Error for ParserError {}
\ No newline at end of file
+/// Longer explanation, with an example and a fix, for an error code
+pub fn explain(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "E0001" => "\
+A character was found that does not start any token.
+
+    let x = 5 @ 3;
+
+Remove the character or replace it with a valid operator.",
+        "E0002" => "\
+A number literal could not be parsed. Exponents need digits and digit
+separators must sit between two digits.
+
+    let x = 1e;
+    let y = 1__000;
+
+Write the number as e.g. `1e3` or `1_000`.",
+        "E0003" => "\
+A multiline comment was opened but never closed. Comments nest, so every
+`/*` needs its own `*/`.
+
+    /* outer /* inner */
+
+Close every opened comment with `*/`.",
+        "E0004" => "\
+A string literal was opened but never closed.
+
+    let greeting = \"hello;
+
+Add the closing quote, or `\"\"\"` for a triple quoted string.",
+        "E0005" => "\
+A string literal contains a raw newline.
+
+    let greeting = \"hello
+    world\";
+
+Use the `\\n` escape sequence or a triple quoted `\"\"\"` string. To only
+break a long string over several lines, end each line with a `\\`.",
+        "E0006" => "\
+An unknown escape sequence was used in a string or character literal.
+
+    let path = \"C:\\data\";
+
+Supported escapes are \\n, \\t, \\r, \\0, \\\\, \\', \\\" and \\u{...}. Escape
+the backslash itself as `\\\\`.",
+        "E0007" => "\
+A character literal must contain exactly one character or escape sequence.
+
+    let empty = '';
+    let long = 'ab';
+
+Use a string literal for anything but a single character.",
+        "E0008" => "\
+A unicode escape must be 1 to 6 hexadecimal digits in braces, naming a valid
+unicode code point.
+
+    let face = \"\\u1F600\";
+    let bad = \"\\u{D800}\";
+
+Write the code point in braces, like `\\u{1F600}`.",
+        "E0009" => "\
+An expression embedded in a string with `${` is never closed by a `}`
+before the string or the code ends.
+
+    print \"hello ${name\";
+
+Close the expression with a `}`, or escape the dollar sign, like `\\${`,
+to write it as text.",
+        "E0010" => "\
+Strings with embedded expressions are nested inside each other deeper than
+the scanner allows. This guards against running out of stack on
+pathological inputs.
+
+    print \"${\"${\"${/* ... thousands more ... */}\"}\"}\";
+
+Build the inner strings separately using variables.",
+        "E0101" => "\
+The parser found a token where it expected the start of an expression.
+
+    let x = * 2;
+
+Check the expression for a missing operand.",
+        "E0102" => "\
+The parser expected a specific token, like a closing parenthesis, but found
+something else.
+
+    let x = (1 + 2;
+
+Add the missing token.",
+        "E0103" => "\
+Every statement must be terminated by a semicolon.
+
+    print 1 + 2
+
+Add a `;` at the end of the statement.",
+        "E0104" => "\
+A name was expected, for example after `let`, but something else was found.
+
+    let = 5;
+
+Give the variable a name, like `let x = 5;`.",
+        "E0105" => "\
+The condition of an `if` or `while` must be wrapped in parentheses.
+
+    while x < 10 x = x + 1;
+
+Write the condition as `while (x < 10)`.",
+        "E0106" => "\
+The parenthesized condition of an `if` or `while` was never closed.
+
+    if (x < 10 print x;
+
+Add the missing `)` after the condition.",
+        "E0107" => "\
+Only variables can be assigned to, the left hand side of `=` was something
+else.
+
+    1 + 2 = 3;
+
+Assign to a variable name, like `x = 3;`.",
+        "E0108" => "\
+A block was opened with `{` but the end of the file was reached before the
+matching `}`.
+
+    while (x < 10) {
+        x = x + 1;
+
+Close the block with a `}`.",
+        "E0109" => "\
+A function can't be declared with more than 255 parameters.
+
+    fn f(a0, a1, a2, /* ... */ a255) {}
+
+Group related parameters into fewer arguments.",
+        "E0110" => "\
+The script contains more expression nodes than allowed by `--max-nodes`.
+This guards against huge, possibly generated, inputs.
+
+    print 1 + 1 + 1 + 1 + /* ... millions more ... */ 1;
+
+Split the expression up, or raise the limit with `--max-nodes`.",
+        "E0111" => "\
+The file ended in the middle of an expression.
+
+    let x = 1 +
+
+Complete the expression.",
+        "E0112" => "\
+A map literal has a key that isn't a string, number or boolean literal.
+
+    let m = { a: 1 };
+
+Quote the keys of map literals that are meant as strings.
+
+    let m = { \"a\": 1 };",
+        "E0113" => "\
+Expressions, or blocks, are nested deeper than the parser allows. This
+guards against running out of stack on pathological inputs.
+
+    print ((((((((((/* ... thousands more ... */ 1))))))))));
+
+Break the expression up using variables.",
+        "E0114" => "\
+A variable is used, but there is no declaration of it anywhere the use
+could see. This is found before the script runs.
+
+    let count = 1;
+    print cuont;
+
+Check the spelling, or declare the variable with `let` before using it.",
+        "E0115" => "\
+A variable is read in the initializer of its own declaration, before it
+has any value.
+
+    let x = x + 1;
+
+Give the variable a value that doesn't depend on itself, or use a
+different name for the new variable.",
+        "E0116" => "\
+The include keyword isn't followed by a string literal with a path.
+
+    include utils;
+
+Quote the path, it is relative to the directory of the including file.
+
+    include \"utils.lhscript\";",
+        "E0201" => "\
+An arithmetic operator was applied to a value that isn't a number.
+
+    print -\"five\";
+
+Only negate, subtract, multiply and divide numbers.",
+        "E0202" => "\
+The script uses a construct the interpreter can parse but not yet run.
+
+    print f(1);
+
+Avoid the construct until the interpreter supports it.",
+        "E0203" => "\
+`+` adds two numbers or concatenates two strings, but never mixes the two.
+Likewise `<`, `<=`, `>` and `>=` compare numbers by value and strings
+lexically, but nothing else.
+
+    print 1 + \"x\";
+
+Make both operands the same type.
+
+Comparisons chain like in math, `1 < x < 3` means `1 < x && x < 3`. A
+comparison in parentheses is a boolean though, so `(1 < x) < 3` fails.",
+        "E0204" => "\
+The right hand side of `/` or `%` was zero. Instead of silently producing
+infinity or NaN this is reported as an error.
+
+    print 10 / 0;
+
+Check the divisor before dividing.",
+        "E0205" => "\
+A variable was read or assigned before it was declared.
+
+    x = 1;
+
+Declare the variable first with `let x = 1;`.",
+        "E0206" => "\
+Something that isn't a function was called.
+
+    let x = 1;
+    x();
+
+Only call functions.",
+        "E0207" => "\
+A function was called with a different number of arguments than it has
+parameters.
+
+    fn add(a, b) { print a + b; }
+    add(1);
+
+Pass one argument per parameter.",
+        "E0208" => "\
+A return statement was executed outside of any function.
+
+    return 1;
+
+Only return from inside a function body.",
+        "E0209" => "\
+A built-in function was called with an argument it can't handle.
+
+    exit(\"done\");
+
+Check what the built-in function expects, exit for example takes a number.",
+        "E0210" => "\
+An array was indexed past its end, or with a negative index.
+
+    let a = [1, 2, 3];
+    print a[3];
+
+Indices start at 0 and must be less than the length of the array.",
+        "E0211" => "\
+An array was indexed with something other than a whole number.
+
+    let a = [1, 2, 3];
+    print a[0.5];
+
+Index arrays with whole numbers only.",
+        "E0212" => "\
+Something other than an array or a map was indexed.
+
+    let a = 1;
+    print a[0];
+
+Only index arrays and maps.",
+        "E0213" => "\
+A break or continue statement was executed outside of any loop. Loops
+don't reach into the functions called from them.
+
+    fn f() { break; }
+    while (true) { f(); }
+
+Only break or continue inside the body of a while or for loop.",
+        "E0214" => "\
+A map was indexed with a key it doesn't contain. Missing keys are an error
+rather than null, to tell them apart from keys set to null.
+
+    let m = { \"a\": 1 };
+    print m[\"b\"];
+
+Only read keys that are in the map.",
+        "E0215" => "\
+A map key, in a map literal or indexing a map, is something other than a
+string, a whole number or a boolean.
+
+    let m = { \"a\": 1 };
+    print m[null];
+
+Use strings, whole numbers or booleans as keys.",
+        "E0216" => "\
+The result of an operation on two integers doesn't fit in an integer.
+Integers are 64 bits and never wrap around.
+
+    print 9223372036854775807 + 1;
+
+Use a float operand, like 1.0, to get an approximate result instead.",
+        "E0217" => "\
+The condition of an assert statement was false, or another falsey value.
+
+    let x = -1;
+    assert(x > 0, \"x must be positive\");
+
+The optional second argument is shown as the reason. Fix the code, or the
+assertion, so the condition holds.",
+        "E0218" => "\
+The script ran more loop iterations than the interpreter allows. The
+limit is set by the host embedding the interpreter, to stop runaway loops.
+
+    while (true) {}
+
+Make sure the loop condition eventually becomes false.",
+        "E0219" => "\
+A file includes itself, directly or through other included files. Running
+it would include the same files over and over.
+
+    // a.lhscript
+    include \"b.lhscript\";
+
+    // b.lhscript
+    include \"a.lhscript\";
+
+Move what both files need into a third file they both include.",
+        "E0220" => "\
+Functions called each other, or themselves, more deeply than the
+interpreter allows. Usually a recursive function never reaches the case
+where it stops calling itself.
+
+    fn f(n) { return f(n + 1); }
+
+Make sure the recursion ends, or rewrite it as a loop.",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scannable;
+
+    #[test]
+    fn error_code() {
+        let err: ScriptError = "x @ y".tokens().unwrap_err().into();
+
+        assert_eq!(err.to_string(), "error[E0001]: Unexpected token at 1:3");
+        assert!(explain("E0001").is_some_and(|text| !text.is_empty()));
+    }
+
+    #[test]
+    fn consume_message() {
+        let tokens = "(1 + 2".tokens().unwrap();
+        let err: ScriptError = crate::parser::Parser::new(tokens).parse().unwrap_err().into();
+
+        assert_eq!(err.to_string(), "error[E0102]: Expected ')' but found end of file at 1:7");
+    }
+
+    #[test]
+    fn multiple_parser_errors() {
+        let err: ScriptError = vec![ParserError::ExpectedSemicolon(Position::new(1, 8)), ParserError::ExpectedIdentifier(Position::new(2, 5))].into();
+
+        assert_eq!(err.to_string(), "error[E0103]: Expected ';' after statement at 1:8\nerror[E0104]: Expected an identifier at 2:5");
+    }
+
+    #[test]
+    fn caret_snippet() {
+        let source = "let x = 1;\nlet @ = 2;\n";
+
+        assert_eq!(snippet(source, Position::new(2, 5)), "2 | let @ = 2;\n  |     ^");
+        assert_eq!(snippet("\tx", Position::new(1, 2)), "1 | \tx\n  | \t^");
+    }
+
+    #[test]
+    fn diagnostic() {
+        let source = "let x = 1;\nlet @ = 2;\n";
+        let err: ScriptError = source.tokens().unwrap_err().into();
+
+        assert_eq!(err.diagnostic(source, None), "error[E0001]: Unexpected token at 2:5\n2 | let @ = 2;\n  |     ^");
+
+        let err: ScriptError = vec![ParserError::ExpectedSemicolon(Position::new(2, 5))].into();
+        assert_eq!(err.diagnostic(source, None), "error[E0103]: Expected ';' after statement at 2:5\n2 | let @ = 2;\n  |     ^");
+    }
+
+    #[test]
+    fn diagnostic_of_other_source() {
+        let source = "let x = 1;\nlet @ = 2;\n";
+        let err: ScriptError = crate::scanner::TokenStream::new(source).named("lib.lhscript").find_map(Result::err).unwrap().into();
+
+        assert_eq!(err.diagnostic(source, Some("lib.lhscript")), "error[E0001]: Unexpected token at lib.lhscript:2:5\n2 | let @ = 2;\n  |     ^");
+
+        // Raised in an included file, while showing the including one
+        assert_eq!(err.diagnostic("include \"lib.lhscript\";", Some("main.lhscript")), "error[E0001]: Unexpected token at lib.lhscript:2:5");
+        assert_eq!(err.diagnostic("include \"lib.lhscript\";", None), "error[E0001]: Unexpected token at lib.lhscript:2:5");
+    }
+
+    #[test]
+    fn exit_code() {
+        let scanner: ScriptError = "@".tokens().unwrap_err().into();
+        let parser: ScriptError = vec![ParserError::ExpectedSemicolon(Position::default())].into();
+        let runtime: ScriptError = RuntimeError::NotCallable.into();
+        let io: ScriptError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+
+        assert_eq!(scanner.exit_code(), 65);
+        assert_eq!(parser.exit_code(), 65);
+        assert_eq!(runtime.exit_code(), 70);
+        assert_eq!(io.exit_code(), 74);
+    }
+
+    #[test]
+    fn every_code_is_explained() {
+        let position = "x".tokens().unwrap()[0].position;
+        let codes = [
+            ScannerError::UnexpectedToken(position).code(),
+            ScannerError::NumberLiteralParsingError(position).code(),
+            ScannerError::UnterminatedMultilineComment(position).code(),
+            ScannerError::UnterminatedString(position).code(),
+            ScannerError::NewlineInString(position).code(),
+            ScannerError::InvalidEscapeSequence(position).code(),
+            ScannerError::InvalidCharLiteral(position).code(),
+            ScannerError::InvalidUnicodeEscape(position).code(),
+            ScannerError::UnterminatedInterpolation(position).code(),
+            ScannerError::InterpolationTooDeep(position).code(),
+            ParserError::Unexpected { found: Token::Eof, position, suggestion: None }.code(),
+            ParserError::Consume { expected: Token::Eof, found: Token::Eof, position, suggestion: None }.code(),
+            ParserError::ExpectedSemicolon(position).code(),
+            ParserError::ExpectedIdentifier(position).code(),
+            ParserError::ExpectedLeftParenthesis("while", position).code(),
+            ParserError::ExpectedRightParenthesis("while", position).code(),
+            ParserError::InvalidAssignmentTarget(position).code(),
+            ParserError::UnterminatedBlock(position).code(),
+            ParserError::TooManyParameters(position).code(),
+            ParserError::TooManyNodes(0, position).code(),
+            ParserError::UnexpectedEof(position).code(),
+            ParserError::ExpectedKey(position).code(),
+            ParserError::TooDeep(0, position).code(),
+            ParserError::UndeclaredVariable { name: String::from("x"), position }.code(),
+            ParserError::ReadInOwnInitializer { name: String::from("x"), position }.code(),
+            ParserError::ExpectedIncludePath(position).code(),
+            RuntimeError::OperandMustBeNumber(position).code(),
+            RuntimeError::Unsupported("calls").code(),
+            RuntimeError::OperandsMustBeNumbersOrStrings(position).code(),
+            RuntimeError::DivisionByZero(position).code(),
+            RuntimeError::UndefinedVariable(String::from("x")).code(),
+            RuntimeError::NotCallable.code(),
+            RuntimeError::ArityMismatch { expected: 0, found: 1 }.code(),
+            RuntimeError::ReturnOutsideFunction.code(),
+            RuntimeError::InvalidArgument("").code(),
+            RuntimeError::IndexOutOfBounds { index: 0, length: 0 }.code(),
+            RuntimeError::InvalidIndex.code(),
+            RuntimeError::NotIndexable.code(),
+            RuntimeError::OutsideLoop("break").code(),
+            RuntimeError::MissingKey(String::from("x")).code(),
+            RuntimeError::InvalidKey.code(),
+            RuntimeError::IntegerOverflow(position).code(),
+            RuntimeError::AssertionFailed { message: None, position }.code(),
+            RuntimeError::ExecutionLimitExceeded(0).code(),
+            RuntimeError::CircularInclude { path: String::from("x"), position }.code(),
+            RuntimeError::StackOverflow(0).code(),
+        ];
+
+        for code in codes {
+            assert!(explain(code).is_some(), "{} is not explained", code);
+        }
+        assert!(explain("E9999").is_none());
+    }
+}
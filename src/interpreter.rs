@@ -0,0 +1,1127 @@
+use std::{cell::RefCell, collections::HashMap, fs::read_to_string, io::{stdout, Write}, path::{Path, PathBuf}, rc::Rc, time::{SystemTime, UNIX_EPOCH}};
+use crate::{ast::*, environment::Environment, errors::{RuntimeError, ScriptError}, parser::Parser, resolver::Resolver, scanner::{Position, TokenStream}, token::{Token, TokenMetadata}, value::{Function, Key, NativeFunction, Value}};
+
+/// Default limit of nested function calls. Each call takes a few KiB of
+/// stack in a debug build, so this keeps clear of overflowing the 2 MiB
+/// stack of a spawned thread
+const MAX_CALL_DEPTH: usize = 200;
+
+/// Tree-walking interpreter evaluating expressions to runtime values
+pub struct Interpreter {
+    environment: Rc<RefCell<Environment>>,
+
+    /// Where print statements write their output
+    output: Box<dyn Write>,
+
+    /// Loop iterations run by the current program
+    iterations: usize,
+
+    /// Upper limit of loop iterations per program, if any
+    max_iterations: Option<usize>,
+
+    /// Function calls currently running, nested in each other
+    depth: usize,
+
+    /// Upper limit of nested function calls, deep recursion fails before
+    /// overflowing the stack of the host
+    max_call_depth: usize,
+
+    /// Files being run, the innermost include last. Includes are relative to
+    /// the directory of the last one
+    files: Vec<PathBuf>,
+}
+
+impl Interpreter {
+    /// Create an interpreter printing to stdout
+    pub fn new() -> Self {
+        Self::with_output(Box::new(stdout()))
+    }
+
+    /// Create an interpreter printing to any writer, like an in-memory buffer
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        let mut interpreter = Interpreter {
+            environment: Rc::new(RefCell::new(Environment::new())),
+            output,
+            iterations: 0,
+            max_iterations: None,
+            depth: 0,
+            max_call_depth: MAX_CALL_DEPTH,
+            files: vec![],
+        };
+
+        interpreter.define_native("clock", 0, clock);
+        interpreter.define_native("exit", 1, exit);
+        interpreter.define_native("str", 1, str);
+        interpreter.define_native("num", 1, num);
+        interpreter
+    }
+
+    /// Limit the number of loop iterations a program may run, so a runaway
+    /// loop can't hang the host
+    pub fn with_max_iterations(mut self, max_iterations: Option<usize>) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Limit the number of function calls nested in each other, lower it for
+    /// a host running scripts on a small stack
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Set the file the next programs are read from, includes in them are
+    /// relative to its directory. Without one they are relative to the
+    /// working directory
+    pub fn set_file(&mut self, path: Option<&Path>) {
+        self.files = path.map(|path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf())).into_iter().collect();
+    }
+
+    /// Scan, parse and run an included file in the current environment. A
+    /// file can't include itself, directly or through other files
+    fn include(&mut self, path: &Path, position: Position) -> Result<(), ScriptError> {
+        let path = match self.files.last().and_then(|file| file.parent()) {
+            Some(directory) => directory.join(path),
+            None => path.to_path_buf(),
+        }.canonicalize()?;
+
+        if self.files.contains(&path) {
+            return Err(RuntimeError::CircularInclude { path: path.display().to_string(), position }.into());
+        }
+
+        let script = read_to_string(&path)?;
+        let tokens = TokenStream::new(&script).named(&path.display().to_string()).collect::<Result<_, _>>()?;
+        let program = Parser::new(tokens).parse_program()?;
+        Resolver::new(self.names()).resolve(&program)?;
+
+        self.files.push(path);
+        let result = program.iter().try_for_each(|statement| self.execute(statement));
+        self.files.pop();
+        result
+    }
+
+    /// Register a built-in function in the global environment
+    pub fn define_native(&mut self, name: &str, arity: usize, function: fn(&[Value]) -> Result<Value, ScriptError>) {
+        let native = NativeFunction { name: String::from(name), arity, function };
+        self.environment.borrow_mut().define(name, Value::NativeFunction(Rc::new(native)));
+    }
+
+    /// Names of every variable in the current scope, built-in functions included
+    pub fn names(&self) -> Vec<String> {
+        self.environment.borrow().bindings().into_iter().map(|(name, _)| String::from(name)).collect()
+    }
+
+    /// Variables of the current scope with their values, ordered by name.
+    /// Built-in functions are left out
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        self.environment.borrow().bindings().into_iter()
+            .filter(|(_, value)| !matches!(value, Value::NativeFunction(_)))
+            .map(|(name, value)| (String::from(name), value.clone()))
+            .collect()
+    }
+
+    /// Execute statements in the given scope. The previous scope is restored
+    /// afterwards, also when a statement fails
+    fn execute_block(&mut self, statements: &[Statement], scope: Environment) -> Result<(), ScriptError> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+
+        let result = statements.iter().try_for_each(|statement| self.execute(statement));
+
+        self.environment = previous;
+        result
+    }
+
+    /// Execute a program, statement by statement, in the interpreter's
+    /// environment. Stops at the first statement failing
+    pub fn interpret(&mut self, program: Vec<Statement>) -> Result<(), ScriptError> {
+        self.iterations = 0;
+
+        for statement in &program {
+            match self.execute(statement) {
+                Err(ScriptError::Return(_)) => return Err(RuntimeError::ReturnOutsideFunction.into()),
+                Err(ScriptError::Break) => return Err(RuntimeError::OutsideLoop("break").into()),
+                Err(ScriptError::Continue) => return Err(RuntimeError::OutsideLoop("continue").into()),
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Execute a single statement
+    pub fn execute(&mut self, statement: &Statement) -> Result<(), ScriptError> {
+        statement.accept(self)
+    }
+
+    /// Evaluate a single expression
+    pub fn evaluate(&mut self, expression: &Expression) -> Result<Value, ScriptError> {
+        expression.accept(self)
+    }
+
+    /// Get the number out of an operand, or fail at the operator's position
+    /// if it's any other type
+    fn number(value: Value, position: Position) -> Result<f64, RuntimeError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            Value::Integer(n) => Ok(n as f64),
+            _ => Err(RuntimeError::OperandMustBeNumber(position)),
+        }
+    }
+
+    /// Apply a binary operator to its evaluated operands
+    fn binary(operator: &TokenMetadata, left: Value, right: Value) -> Result<Value, ScriptError> {
+        let position = operator.position;
+
+        let (left, right) = match operator.token {
+            // Plus is overloaded for string concatenation, but never mixes strings and numbers
+            Token::Plus => match (left, right) {
+                (Value::Str(l), Value::Str(r)) => return Ok(Value::Str(l + &r)),
+                (l @ (Value::Number(_) | Value::Integer(_)), r @ (Value::Number(_) | Value::Integer(_))) => (l, r),
+                _ => return Err(RuntimeError::OperandsMustBeNumbersOrStrings(position).into()),
+            },
+
+            // Values of different types are never equal, apart from integers and floats
+            Token::EqualEqual => return Ok(Value::Bool(left.equals(&right))),
+            Token::BangEqual => return Ok(Value::Bool(!left.equals(&right))),
+
+            Token::Greater | Token::GreaterEqual | Token::Less | Token::LessEqual => {
+                let ordering = left.compare(&right, position)?;
+                return Ok(Value::Bool(ordering.is_some_and(|ordering| match operator.token {
+                    Token::Greater => ordering.is_gt(),
+                    Token::GreaterEqual => ordering.is_ge(),
+                    Token::Less => ordering.is_lt(),
+                    _ => ordering.is_le(),
+                })));
+            },
+            _ => (left, right),
+        };
+
+        // Integers only stay integers among themselves, mixed with a float they are promoted
+        if let (Value::Integer(l), Value::Integer(r)) = (&left, &right) {
+            return Self::integer_binary(&operator.token, *l, *r, position);
+        }
+
+        let (l, r) = (Self::number(left, position)?, Self::number(right, position)?);
+
+        if matches!(operator.token, Token::Slash | Token::Percent) && r == 0f64 {
+            return Err(RuntimeError::DivisionByZero(position).into());
+        }
+
+        Ok(match operator.token {
+            Token::Plus => Value::Number(l + r),
+            Token::Minus => Value::Number(l - r),
+            Token::Star => Value::Number(l * r),
+            Token::Slash => Value::Number(l / r),
+            Token::Percent => Value::Number(l % r),
+            Token::StarStar => Value::Number(l.powf(r)),
+            _ => return Err(RuntimeError::Unsupported("this binary operator").into()),
+        })
+    }
+
+    /// Operators that can be chained, see comparison
+    fn is_comparison(token: &Token) -> bool {
+        matches!(token, Token::Greater | Token::GreaterEqual | Token::Less | Token::LessEqual)
+    }
+
+    /// Evaluate a comparison, returning its result along with its right operand.
+    /// Chained comparisons, like 1 < x <= 3, mean 1 < x && x <= 3 with x only
+    /// evaluated once. Parenthesized comparisons aren't chained
+    fn comparison(&mut self, expr: &BinaryExpression) -> Result<(Value, Value), ScriptError> {
+        let left = match expr.left.as_ref() {
+            Expression::Binary(left) if Self::is_comparison(&left.operator.token) => {
+                let (result, operand) = self.comparison(left)?;
+                if !result.is_truthy() {
+                    // Once a link is false, the rest of the chain isn't evaluated
+                    return Ok((result, operand));
+                }
+                operand
+            },
+            left => left.accept(self)?,
+        };
+        let right = expr.right.accept(self)?;
+
+        Ok((Self::binary(&expr.operator, left, right.clone())?, right))
+    }
+
+    /// Apply a binary operator to two integers. Fails on overflow rather than
+    /// wrapping around, division and negative powers give floats
+    fn integer_binary(operator: &Token, l: i64, r: i64, position: Position) -> Result<Value, ScriptError> {
+        if matches!(operator, Token::Slash | Token::Percent) && r == 0 {
+            return Err(RuntimeError::DivisionByZero(position).into());
+        }
+
+        let result = match operator {
+            Token::Plus => l.checked_add(r),
+            Token::Minus => l.checked_sub(r),
+            Token::Star => l.checked_mul(r),
+            Token::Percent => l.checked_rem(r),
+            Token::StarStar if r >= 0 => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+            Token::StarStar => return Ok(Value::Number((l as f64).powf(r as f64))),
+            Token::Slash => return Ok(Value::Number(l as f64 / r as f64)),
+            _ => return Err(RuntimeError::Unsupported("this binary operator").into()),
+        };
+
+        result.map(Value::Integer).ok_or(RuntimeError::IntegerOverflow(position).into())
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Seconds since the unix epoch
+fn clock(_arguments: &[Value]) -> Result<Value, ScriptError> {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(Value::Number(elapsed.as_secs_f64()))
+}
+
+/// Stop the script with the given exit code
+fn exit(arguments: &[Value]) -> Result<Value, ScriptError> {
+    match arguments {
+        [Value::Integer(code)] => Err(ScriptError::Exit(*code as i32)),
+        [Value::Number(code)] => Err(ScriptError::Exit(*code as i32)),
+        _ => Err(RuntimeError::InvalidArgument("the exit code must be a number").into()),
+    }
+}
+
+/// Any value as a string, the way print shows it
+fn str(arguments: &[Value]) -> Result<Value, ScriptError> {
+    Ok(Value::Str(arguments[0].to_string()))
+}
+
+/// Parse a string to a number, an integer if it's a whole number that fits
+fn num(arguments: &[Value]) -> Result<Value, ScriptError> {
+    let [Value::Str(string)] = arguments else {
+        return Err(RuntimeError::InvalidArgument("only strings can be parsed to numbers").into());
+    };
+
+    let string = string.trim();
+    if let Ok(integer) = string.parse() {
+        return Ok(Value::Integer(integer));
+    }
+
+    match string.parse() {
+        Ok(number) => Ok(Value::Number(number)),
+        Err(_) => Err(RuntimeError::InvalidArgument("the string is not a number").into()),
+    }
+}
+
+impl StatementVisitor<()> for Interpreter {
+    fn visit_expression_statement(&mut self, expression: &Expression) -> Result<(), ScriptError> {
+        self.evaluate(expression)?;
+        Ok(())
+    }
+
+    fn visit_print(&mut self, expressions: &[Expression]) -> Result<(), ScriptError> {
+        let mut values = vec![];
+        for expression in expressions {
+            values.push(self.evaluate(expression)?.to_string());
+        }
+
+        writeln!(self.output, "{}", values.join(" "))?;
+        Ok(())
+    }
+
+    fn visit_assert(&mut self, condition: &Expression, message: Option<&Expression>, position: Position) -> Result<(), ScriptError> {
+        if self.evaluate(condition)?.is_truthy() {
+            return Ok(());
+        }
+
+        // The message is only evaluated when the assertion fails
+        let message = match message {
+            Some(message) => Some(self.evaluate(message)?.to_string()),
+            None => None,
+        };
+
+        Err(RuntimeError::AssertionFailed { message, position }.into())
+    }
+
+    fn visit_include(&mut self, path: &str, position: Position) -> Result<(), ScriptError> {
+        self.include(Path::new(path), position)
+    }
+
+    fn visit_let(&mut self, name: &str, initializer: Option<&Expression>) -> Result<(), ScriptError> {
+        let value = match initializer {
+            Some(initializer) => self.evaluate(initializer)?,
+            None => Value::Null,
+        };
+
+        self.environment.borrow_mut().define(name, value);
+        Ok(())
+    }
+
+    fn visit_if(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) -> Result<(), ScriptError> {
+        if self.evaluate(condition)?.is_truthy() {
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_while(&mut self, condition: &Expression, body: &Statement, increment: Option<&Expression>) -> Result<(), ScriptError> {
+        while self.evaluate(condition)?.is_truthy() {
+            self.iterations += 1;
+            if let Some(max) = self.max_iterations.filter(|&max| self.iterations > max) {
+                return Err(RuntimeError::ExecutionLimitExceeded(max).into());
+            }
+
+            match self.execute(body) {
+                Err(ScriptError::Break) => break,
+                Ok(()) | Err(ScriptError::Continue) => {},
+                Err(err) => return Err(err),
+            }
+
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) -> Result<(), ScriptError> {
+        let scope = Environment::with_enclosing(self.environment.clone());
+        self.execute_block(statements, scope)
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<(), ScriptError> {
+        let function = Function {
+            name: String::from(name),
+            params: params.to_vec(),
+            body: body.to_vec(),
+            closure: self.environment.clone(),
+        };
+
+        self.environment.borrow_mut().define(name, Value::Function(Rc::new(function)));
+        Ok(())
+    }
+
+    fn visit_return(&mut self, value: Option<&Expression>) -> Result<(), ScriptError> {
+        let value = match value {
+            Some(value) => self.evaluate(value)?,
+            None => Value::Null,
+        };
+
+        // Unwinds through the statements of the body up to the call
+        Err(ScriptError::Return(value))
+    }
+
+    fn visit_break(&mut self) -> Result<(), ScriptError> {
+        Err(ScriptError::Break)
+    }
+
+    fn visit_continue(&mut self) -> Result<(), ScriptError> {
+        Err(ScriptError::Continue)
+    }
+}
+
+impl ExpressionVisitor<Value> for Interpreter {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<Value, ScriptError> {
+        let right = expr.right.accept(self)?;
+
+        let position = expr.operator.position;
+
+        match expr.operator.token {
+            Token::Minus => match right {
+                Value::Integer(n) => n.checked_neg().map(Value::Integer).ok_or(RuntimeError::IntegerOverflow(position).into()),
+                right => Ok(Value::Number(-Self::number(right, position)?)),
+            },
+            Token::Bang => Ok(Value::Bool(!right.is_truthy())),
+            _ => Err(RuntimeError::Unsupported("this unary operator").into()),
+        }
+    }
+
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<Value, ScriptError> {
+        if Self::is_comparison(&expr.operator.token) {
+            return Ok(self.comparison(expr)?.0);
+        }
+
+        let left = expr.left.accept(self)?;
+        let right = expr.right.accept(self)?;
+
+        Self::binary(&expr.operator, left, right)
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<Value, ScriptError> {
+        let left = expr.left.accept(self)?;
+
+        // The right operand is only evaluated if the left doesn't decide the result
+        let short_circuit = match expr.operator.token {
+            Token::Or => left.is_truthy(),
+            Token::And => !left.is_truthy(),
+            _ => return Err(RuntimeError::Unsupported("this logical operator").into()),
+        };
+
+        if short_circuit {
+            Ok(left)
+        } else {
+            expr.right.accept(self)
+        }
+    }
+
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<Value, ScriptError> {
+        // The right operand is only evaluated if the left is null
+        match expr.left.accept(self)? {
+            Value::Null => expr.right.accept(self),
+            left => Ok(left),
+        }
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<Value, ScriptError> {
+        expr.group.accept(self)
+    }
+
+    fn visit_literal(&mut self, expr: &LiteralExpression) -> Result<Value, ScriptError> {
+        Ok(match &expr.literal {
+            Token::Number(n) => Value::Number(*n),
+            Token::Integer(n) => Value::Integer(*n),
+            Token::String(s) => Value::Str(s.clone()),
+            Token::True => Value::Bool(true),
+            Token::False => Value::Bool(false),
+            Token::Null => Value::Null,
+            _ => return Err(RuntimeError::Unsupported("this literal").into()),
+        })
+    }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<Value, ScriptError> {
+        Ok(self.environment.borrow().get(&expr.name)?)
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<Value, ScriptError> {
+        let value = expr.value.accept(self)?;
+        self.environment.borrow_mut().assign(&expr.name, value.clone())?;
+
+        Ok(value)
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<Value, ScriptError> {
+        let token = match expr.operator.token {
+            Token::PlusPlus => Token::Plus,
+            Token::MinusMinus => Token::Minus,
+            _ => return Err(RuntimeError::Unsupported("this increment operator").into()),
+        };
+
+        // The variable is updated like x = x + 1, but the old value is the result
+        let old = self.environment.borrow().get(&expr.name)?;
+        let operator = TokenMetadata { token, ..expr.operator.clone() };
+        let new = Self::binary(&operator, old.clone(), Value::Integer(1))?;
+        self.environment.borrow_mut().assign(&expr.name, new)?;
+
+        Ok(old)
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<Value, ScriptError> {
+        let callee = expr.callee.accept(self)?;
+
+        let mut arguments = vec![];
+        for argument in &expr.arguments {
+            arguments.push(argument.accept(self)?);
+        }
+
+        let function = match callee {
+            Value::Function(function) => function,
+            Value::NativeFunction(native) => {
+                if arguments.len() != native.arity {
+                    return Err(RuntimeError::ArityMismatch { expected: native.arity, found: arguments.len() }.into());
+                }
+
+                return (native.function)(&arguments);
+            }
+            _ => return Err(RuntimeError::NotCallable.into()),
+        };
+
+        if arguments.len() != function.params.len() {
+            return Err(RuntimeError::ArityMismatch { expected: function.params.len(), found: arguments.len() }.into());
+        }
+
+        // Parameters live in a fresh scope enclosed by the function's closure
+        let mut scope = Environment::with_enclosing(function.closure.clone());
+        for (param, argument) in function.params.iter().zip(arguments) {
+            scope.define(param, argument);
+        }
+
+        if self.depth >= self.max_call_depth {
+            return Err(RuntimeError::StackOverflow(self.max_call_depth).into());
+        }
+
+        self.depth += 1;
+        let result = self.execute_block(&function.body, scope);
+        self.depth -= 1;
+
+        match result {
+            Ok(()) => Ok(Value::Null),
+            Err(ScriptError::Return(value)) => Ok(value),
+            // Loops around the call can't be broken out of from inside it
+            Err(ScriptError::Break) => Err(RuntimeError::OutsideLoop("break").into()),
+            Err(ScriptError::Continue) => Err(RuntimeError::OutsideLoop("continue").into()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<Value, ScriptError> {
+        let mut elements = vec![];
+        for element in &expr.elements {
+            elements.push(element.accept(self)?);
+        }
+
+        Ok(Value::Array(elements))
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<Value, ScriptError> {
+        // A lambda is a function returning the value of its body
+        let function = Function {
+            name: String::from("lambda"),
+            params: expr.params.clone(),
+            body: vec![Statement::Return(Some(expr.body.as_ref().clone()))],
+            closure: self.environment.clone(),
+        };
+
+        Ok(Value::Function(Rc::new(function)))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<Value, ScriptError> {
+        let scope = Environment::with_enclosing(self.environment.clone());
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+
+        // The value is the tail expression, evaluated in the block's scope
+        let result = expr.statements.iter().try_for_each(|statement| self.execute(statement))
+            .and_then(|_| match &expr.tail {
+                Some(tail) => self.evaluate(tail),
+                None => Ok(Value::Null),
+            });
+
+        self.environment = previous;
+        result
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<Value, ScriptError> {
+        let mut entries = HashMap::new();
+        for (key, value) in &expr.entries {
+            entries.insert(Key::try_from(key.accept(self)?)?, value.accept(self)?);
+        }
+
+        Ok(Value::Map(entries))
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<Value, ScriptError> {
+        let mut string = String::new();
+        for part in &expr.parts {
+            string.push_str(&part.accept(self)?.to_string());
+        }
+
+        Ok(Value::Str(string))
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<Value, ScriptError> {
+        let target = expr.target.accept(self)?;
+        let index = expr.index.accept(self)?;
+
+        let elements = match target {
+            Value::Array(elements) => elements,
+            Value::Map(mut entries) => {
+                let key = Key::try_from(index)?;
+                return entries.remove(&key).ok_or_else(|| RuntimeError::MissingKey(key.to_string()).into());
+            },
+            _ => return Err(RuntimeError::NotIndexable.into()),
+        };
+        let index = match index {
+            Value::Integer(index) => index,
+            Value::Number(index) if index.fract() == 0f64 => index as i64,
+            _ => return Err(RuntimeError::InvalidIndex.into()),
+        };
+
+        match usize::try_from(index).ok().and_then(|i| elements.get(i)) {
+            Some(element) => Ok(element.clone()),
+            None => Err(RuntimeError::IndexOutOfBounds { index, length: elements.len() }.into()),
+        }
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<Value, ScriptError> {
+        // No value has members until there are classes
+        expr.object.accept(self)?;
+        Err(RuntimeError::Unsupported("member access").into())
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    /// Writer sharing its buffer, so the output can be read after the
+    /// interpreter took ownership of the writer
+    #[derive(Clone, Default)]
+    pub(crate) struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        pub(crate) fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    fn run(code: &str) -> String {
+        let buffer = SharedBuffer::default();
+        let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+
+        Interpreter::with_output(Box::new(buffer.clone())).interpret(program).unwrap();
+        buffer.contents()
+    }
+
+    fn evaluate(code: &str) -> Result<Value, ScriptError> {
+        let expression = Parser::new(code.tokens().unwrap()).parse().unwrap();
+        Interpreter::new().evaluate(&expression)
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(evaluate("1 * (2 + 3)").unwrap(), Value::Number(5f64));
+        assert_eq!(evaluate("2 * (1 + 2)").unwrap(), Value::Number(6f64));
+        assert_eq!(evaluate("-5 + 2").unwrap(), Value::Number(-3f64));
+        assert_eq!(evaluate("7 / 2 - 1").unwrap(), Value::Number(2.5f64));
+    }
+
+    #[test]
+    fn integers() {
+        assert!(matches!(evaluate("9007199254740993").unwrap(), Value::Integer(9007199254740993)));
+        assert!(matches!(evaluate("9007199254740992 + 1").unwrap(), Value::Integer(9007199254740993)));
+        assert!(matches!(evaluate("2 ** 62").unwrap(), Value::Integer(4611686018427387904)));
+        assert!(matches!(evaluate("1 + 0.5").unwrap(), Value::Number(n) if n == 1.5f64));
+        assert!(matches!(evaluate("6 / 3").unwrap(), Value::Number(n) if n == 2f64));
+        assert_eq!(evaluate("1 == 1.0").unwrap(), Value::Bool(true));
+        assert_eq!(run("print 9007199254740993; print 2 ** -1;"), "9007199254740993\n0.5\n");
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let overflow = |code: &str| matches!(evaluate(code), Err(ScriptError::RuntimeError(RuntimeError::IntegerOverflow(_))));
+
+        assert!(overflow(&format!("{} + 1", i64::MAX)));
+        assert!(overflow(&format!("-{} - 2", i64::MAX)));
+        assert!(overflow("3037000500 * 3037000500"));
+        assert!(overflow("2 ** 63"));
+        assert!(matches!(evaluate(&format!("{} + 1.0", i64::MAX)), Ok(Value::Number(_))));
+    }
+
+    #[test]
+    fn power() {
+        assert_eq!(evaluate("2 ** 3 ** 2").unwrap(), Value::Number(512f64));
+        assert_eq!(evaluate("3 * 2 ** 2").unwrap(), Value::Number(12f64));
+        assert_eq!(evaluate("4 ** 0.5").unwrap(), Value::Number(2f64));
+
+        let err = evaluate("2 ** \"x\"").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::OperandMustBeNumber(_))));
+    }
+
+    #[test]
+    fn addition_and_concatenation() {
+        assert_eq!(evaluate("1 + 2").unwrap(), Value::Number(3f64));
+        assert_eq!(evaluate("\"a\" + \"b\"").unwrap(), Value::Str(String::from("ab")));
+        assert!(matches!(evaluate("1 + \"x\""), Err(ScriptError::RuntimeError(RuntimeError::OperandsMustBeNumbersOrStrings(_)))));
+    }
+
+    #[test]
+    fn comparison() {
+        assert_eq!(evaluate("1 < 2").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("3 >= 3").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("3 > 3").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("4 <= 3").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("\"a\" < \"b\"").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("\"b\" <= \"abc\"").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("2 > 1.5").unwrap(), Value::Bool(true));
+        assert!(matches!(evaluate("\"a\" < 1"), Err(ScriptError::RuntimeError(RuntimeError::OperandsMustBeNumbersOrStrings(_)))));
+        assert!(matches!(evaluate("null >= null"), Err(ScriptError::RuntimeError(RuntimeError::OperandsMustBeNumbersOrStrings(_)))));
+    }
+
+    #[test]
+    fn chained_comparison() {
+        assert_eq!(evaluate("1 < 2 < 3").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("3 > 2 > 1").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("1 < 3 < 2").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("1 <= 1 < 2 >= 0").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("2 < 1 < \"x\"").unwrap(), Value::Bool(false));
+
+        // The middle operand is only evaluated once
+        let output = run("let n = 0; fn f() { n = n + 1; return n; } print 0 < f() < 2; print n;");
+        assert_eq!(output, "true\n1\n");
+
+        // Parentheses compare the result of the inner comparison
+        assert!(matches!(evaluate("(1 < 2) < 3"), Err(ScriptError::RuntimeError(RuntimeError::OperandsMustBeNumbersOrStrings(_)))));
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(evaluate("null == null").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("null == 0").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("\"1\" != 1").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("1 + 1 == 2").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("\"a\" == \"a\"").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn error_position() {
+        let err = evaluate("1 < \"x\"").unwrap_err();
+        assert_eq!(err.to_string(), "error[E0203]: Operands must be two numbers or two strings at 1:3");
+
+        let err = evaluate("1 +\n  2 < \"x\"").unwrap_err();
+        assert_eq!(err.to_string(), "error[E0203]: Operands must be two numbers or two strings at 2:5");
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(evaluate("1 / 2").unwrap(), Value::Number(0.5f64));
+        assert_eq!(evaluate("7 % 4").unwrap(), Value::Number(3f64));
+        assert!(matches!(evaluate("1 / 0"), Err(ScriptError::RuntimeError(RuntimeError::DivisionByZero(_)))));
+        assert!(matches!(evaluate("1 % (2 - 2)"), Err(ScriptError::RuntimeError(RuntimeError::DivisionByZero(_)))));
+    }
+
+    #[test]
+    fn modulo() {
+        assert_eq!(evaluate("7 % 3").unwrap(), Value::Number(1f64));
+        // The remainder keeps the sign of the dividend, like in Rust
+        assert_eq!(evaluate("-7 % 3").unwrap(), Value::Number(-1f64));
+        assert!(matches!(evaluate("7 % 0"), Err(ScriptError::RuntimeError(RuntimeError::DivisionByZero(_)))));
+
+        // Integral results print without a fraction
+        assert_eq!(run("print 4 % 2; print 7.5 % 2;"), "0\n1.5\n");
+    }
+
+    #[test]
+    fn variables() {
+        let program = Parser::new("let x = 1; let y; x = x + 1;".tokens().unwrap()).parse_program().unwrap();
+        let mut interpreter = Interpreter::new();
+        for statement in &program {
+            interpreter.execute(statement).unwrap();
+        }
+
+        let mut read = |name: &str| interpreter.evaluate(&Parser::new(name.tokens().unwrap()).parse().unwrap());
+        assert_eq!(read("x").unwrap(), Value::Number(2f64));
+        assert_eq!(read("y").unwrap(), Value::Null);
+        assert!(matches!(read("z"), Err(ScriptError::RuntimeError(RuntimeError::UndefinedVariable(_)))));
+        assert!(matches!(read("z = 1"), Err(ScriptError::RuntimeError(RuntimeError::UndefinedVariable(_)))));
+    }
+
+    #[test]
+    fn block_scope() {
+        let code = "let x = 1; let y = 1; { let x = 2; y = x; }";
+        let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+        let mut interpreter = Interpreter::new();
+        for statement in &program {
+            interpreter.execute(statement).unwrap();
+        }
+
+        let mut read = |name: &str| interpreter.evaluate(&Parser::new(name.tokens().unwrap()).parse().unwrap());
+        assert_eq!(read("x").unwrap(), Value::Number(1f64));
+        assert_eq!(read("y").unwrap(), Value::Number(2f64));
+    }
+
+    #[test]
+    fn interpret() {
+        let program = Parser::new("let x = 2; print x + 1;".tokens().unwrap()).parse_program().unwrap();
+        let mut interpreter = Interpreter::new();
+
+        interpreter.interpret(program).unwrap();
+        assert_eq!(interpreter.evaluate(&Parser::new("x + 1".tokens().unwrap()).parse().unwrap()).unwrap(), Value::Number(3f64));
+
+        assert_eq!(run("let x = 2; print x + 1;"), "3\n");
+    }
+
+    #[test]
+    fn print_to_buffer() {
+        assert_eq!(run("print \"hi\";"), "hi\n");
+        assert_eq!(run("print 1; print true; print null;"), "1\ntrue\nnull\n");
+    }
+
+    #[test]
+    fn template_strings() {
+        let code = r#"let name = "Ada"; let age = 36; print "hello ${name}, you are ${age} years old";"#;
+        assert_eq!(run(code), "hello Ada, you are 36 years old\n");
+        assert_eq!(run(r#"print "no placeholders";"#), "no placeholders\n");
+        assert_eq!(run(r#"print "${[1, 2]} ${"x${1 + 1}"} ${null}";"#), "[1, 2] x2 null\n");
+    }
+
+    #[test]
+    fn block_expressions() {
+        assert_eq!(run("let x = 10; let y = { let x = 1; x + 1 }; print x, y;"), "10 2\n");
+        assert_eq!(run("let y = { let x = 1; x + 1; }; print y;"), "null\n");
+        assert_eq!(run("fn f(n) { return { if (n > 0) return 1; n - 1 }; } print f(1), f(0);"), "1 -1\n");
+    }
+
+    #[test]
+    fn print_several() {
+        assert_eq!(run("print 1, \"a\", true, [2, 3];"), "1 a true [2, 3]\n");
+        assert_eq!(run("print; print 1;"), "\n1\n");
+    }
+
+    #[test]
+    fn control_flow() {
+        let code = "let n = 0; let sum = 0; while (n < 4) { n = n + 1; if (n == 2) sum = sum + 10; else sum = sum + n; }";
+        let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+        let mut interpreter = Interpreter::new();
+
+        interpreter.interpret(program).unwrap();
+        assert_eq!(interpreter.evaluate(&Parser::new("sum".tokens().unwrap()).parse().unwrap()).unwrap(), Value::Number(18f64));
+    }
+
+    #[test]
+    fn functions() {
+        let code = "let product; fn multiply(a, b) { product = a * b; } print multiply(3, 4); print product;";
+        assert_eq!(run(code), "null\n12\n");
+
+        let code = "fn counter() { let n = 0; fn increment() { n = n + 1; print n; } increment(); increment(); } counter();";
+        assert_eq!(run(code), "1\n2\n");
+    }
+
+    #[test]
+    fn return_value() {
+        assert_eq!(run("fn f() { return 1; return 2; } print f();"), "1\n");
+        assert_eq!(run("fn f(n) { while (true) { if (n > 3) return n; n = n + 1; } } print f(0);"), "4\n");
+        assert_eq!(run("fn f() { return; } print f();"), "null\n");
+
+        let program = Parser::new("return 1;".tokens().unwrap()).parse_program().unwrap();
+        let err = Interpreter::new().interpret(program).unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::ReturnOutsideFunction)));
+    }
+
+    #[test]
+    fn break_and_continue() {
+        assert_eq!(run("while (true) { break; } print 1;"), "1\n");
+        assert_eq!(run("let i = 0; while (i < 5) { i = i + 1; if (i % 2 == 0) continue; print i; }"), "1\n3\n5\n");
+        assert_eq!(run("for (let i = 0; i < 5; i = i + 1) { if (i == 1) continue; if (i == 3) break; print i; }"), "0\n2\n");
+        assert_eq!(run("for (let i = 0; i < 2; i = i + 1) for (;;) { print i; break; }"), "0\n1\n");
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        let error = |code: &str| {
+            let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+            Interpreter::new().interpret(program).unwrap_err()
+        };
+
+        assert!(matches!(error("break;"), ScriptError::RuntimeError(RuntimeError::OutsideLoop("break"))));
+        assert!(matches!(error("{ continue; }"), ScriptError::RuntimeError(RuntimeError::OutsideLoop("continue"))));
+        assert!(matches!(error("fn f() { break; } while (true) f();"), ScriptError::RuntimeError(RuntimeError::OutsideLoop("break"))));
+    }
+
+    #[test]
+    fn logical_operators() {
+        assert_eq!(evaluate("null || 5").unwrap(), Value::Number(5f64));
+        assert_eq!(evaluate("\"a\" || 5").unwrap(), Value::Str(String::from("a")));
+        assert_eq!(evaluate("null && 5").unwrap(), Value::Null);
+        assert_eq!(evaluate("1 && 2").unwrap(), Value::Number(2f64));
+        assert_eq!(evaluate("false or true").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn short_circuit() {
+        assert_eq!(run("let x = 0; false && (x = 1); print x;"), "0\n");
+        assert_eq!(run("let x = 0; true || (x = 1); print x;"), "0\n");
+        assert_eq!(run("let x = 0; true && (x = 1); print x;"), "1\n");
+        assert_eq!(run("let x = 0; false || (x = 1); print x;"), "1\n");
+    }
+
+    #[test]
+    fn coalesce() {
+        assert_eq!(evaluate("null ?? 5").unwrap(), Value::Number(5f64));
+        assert_eq!(evaluate("false ?? 5").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("null ?? null ?? \"a\"").unwrap(), Value::Str(String::from("a")));
+        assert_eq!(run("let x = 0; 1 ?? (x = 1); print x;"), "0\n");
+        assert_eq!(run("let x = 0; null ?? (x = 1); print x;"), "1\n");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(evaluate("[1, \"a\", 1 + 1]").unwrap(), Value::Array(vec![
+            Value::Number(1f64),
+            Value::Str(String::from("a")),
+            Value::Number(2f64),
+        ]));
+        assert_eq!(evaluate("[10, 20, 30][1]").unwrap(), Value::Number(20f64));
+        assert_eq!(run("let a = [[1], [2, 3]]; print a[1][0]; print a;"), "2\n[[1], [2, 3]]\n");
+    }
+
+    #[test]
+    fn index_errors() {
+        let error = |code: &str| match evaluate(code).unwrap_err() {
+            ScriptError::RuntimeError(err) => err,
+            err => panic!("Expected a runtime error, got {:?}", err),
+        };
+
+        assert!(matches!(error("[1, 2][2]"), RuntimeError::IndexOutOfBounds { length: 2, .. }));
+        assert!(matches!(error("[1, 2][-1]"), RuntimeError::IndexOutOfBounds { .. }));
+        assert!(matches!(error("[1, 2][0.5]"), RuntimeError::InvalidIndex));
+        assert!(matches!(error("[1, 2][\"0\"]"), RuntimeError::InvalidIndex));
+        assert!(matches!(error("1[0]"), RuntimeError::NotIndexable));
+    }
+
+    #[test]
+    fn maps() {
+        assert_eq!(evaluate("{\"a\": 1, \"b\": 1 + 1}").unwrap(), Value::Map(HashMap::from([
+            (Key::Str(String::from("a")), Value::Number(1f64)),
+            (Key::Str(String::from("b")), Value::Number(2f64)),
+        ])));
+        assert_eq!(run("let m = { \"x\": [1, 2] }; print m[\"x\"][1]; print m;"), "2\n{x: [1, 2]}\n");
+
+        let err = evaluate("{\"a\": 1}[\"b\"]").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::MissingKey(key)) if key == "b"));
+        let err = evaluate("{\"a\": 1}[0]").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::MissingKey(key)) if key == "0"));
+        let err = evaluate("{\"a\": 1}[null]").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::InvalidKey)));
+        let err = evaluate("{\"a\": 1}[clock]").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::InvalidKey)));
+        let err = evaluate("{1.5: 1}").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::InvalidKey)));
+    }
+
+    #[test]
+    fn map_keys() {
+        assert_eq!(evaluate("{1: \"one\", 2: \"two\"}[2]").unwrap(), Value::Str(String::from("two")));
+        assert_eq!(evaluate("{1: \"one\"}[1.0]").unwrap(), Value::Str(String::from("one")));
+        assert_eq!(evaluate("{true: 1, false: 0}[1 > 2]").unwrap(), Value::Integer(0));
+        assert_eq!(run("print {\"b\": 1, 2: 2, true: 3};"), "{true: 3, 2: 2, b: 1}\n");
+    }
+
+    #[test]
+    fn native_functions() {
+        assert!(matches!(evaluate("clock()").unwrap(), Value::Number(seconds) if seconds > 0f64));
+        assert_eq!(run("print clock;"), "<native fn clock>\n");
+
+        let err = evaluate("clock(1)").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::ArityMismatch { expected: 0, found: 1 })));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(evaluate("str(42)").unwrap(), Value::Str(String::from("42")));
+        assert_eq!(evaluate("str([1, null]) + \"!\"").unwrap(), Value::Str(String::from("[1, null]!")));
+        assert_eq!(evaluate("num(\"3.14\") == 3.14").unwrap(), Value::Bool(true));
+        assert!(matches!(evaluate("num(\" 42 \")").unwrap(), Value::Integer(42)));
+        assert_eq!(evaluate("num(str(2.5)) * 2").unwrap(), Value::Number(5f64));
+
+        assert!(matches!(evaluate("num(\"abc\")"), Err(ScriptError::RuntimeError(RuntimeError::InvalidArgument(_)))));
+        assert!(matches!(evaluate("num(1)"), Err(ScriptError::RuntimeError(RuntimeError::InvalidArgument(_)))));
+    }
+
+    #[test]
+    fn lambdas() {
+        assert!(matches!(evaluate("((a, b) -> a + b)(1, 2)").unwrap(), Value::Integer(3)));
+        assert!(matches!(evaluate("((x) -> (y) -> x * y)(2)(3)").unwrap(), Value::Integer(6)));
+
+        let program = Parser::new("let n = 10; let add = (x) -> x + n; print add(1);".tokens().unwrap()).parse_program().unwrap();
+        assert!(Interpreter::new().interpret(program).is_ok());
+    }
+
+    #[test]
+    fn asserts() {
+        let interpret = |code: &str| Interpreter::new().interpret(Parser::new(code.tokens().unwrap()).parse_program().unwrap());
+
+        assert!(interpret("assert(true); assert(1 < 2, \"math\");").is_ok());
+
+        let err = interpret("let x = 1;\nassert(x == 2);").unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::AssertionFailed { message: None, position }) if position == Position::new(2, 8)));
+
+        let err = interpret("let x = -1; assert(x > 0, \"x must be \" + \"positive\");").unwrap_err();
+        assert_eq!(err.to_string(), "error[E0217]: Assertion failed at 1:20: x must be positive");
+    }
+
+    #[test]
+    fn let_several() {
+        assert_eq!(run("let a = 1, b = a + 1, c; print a; print b; print c;"), "1\n2\nnull\n");
+        assert_eq!(run("for (let i = 0, j = 3; i < j; i++) print i * j;"), "0\n3\n6\n");
+    }
+
+    #[test]
+    fn increments() {
+        assert_eq!(run("let x = 1; print x++; print x; print x--; print x;"), "1\n2\n2\n1\n");
+        assert_eq!(run("let x = 0.5; x++; print x;"), "1.5\n");
+        assert_eq!(run("let n = 0; for (let i = 0; i < 3; i++) n++; print n;"), "3\n");
+
+        assert!(matches!(evaluate("y++"), Err(ScriptError::RuntimeError(RuntimeError::UndefinedVariable(_)))));
+    }
+
+    #[test]
+    fn iteration_limit() {
+        let interpret = |code: &str| {
+            let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+            Interpreter::new().with_max_iterations(Some(1000)).interpret(program)
+        };
+
+        assert!(matches!(interpret("while (true) {}"), Err(ScriptError::RuntimeError(RuntimeError::ExecutionLimitExceeded(1000)))));
+        assert!(matches!(interpret("for (let i = 0; i < 10; i = i + 1) { for (;;) {} }"), Err(ScriptError::RuntimeError(RuntimeError::ExecutionLimitExceeded(1000)))));
+        assert!(interpret("for (let i = 0; i < 1000; i = i + 1) {}").is_ok());
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let interpret = |code: &str, mut interpreter: Interpreter| {
+            let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+            interpreter.interpret(program)
+        };
+
+        let result = interpret("fn f(n) { return f(n + 1); } f(0);", Interpreter::new());
+        assert!(matches!(result, Err(ScriptError::RuntimeError(RuntimeError::StackOverflow(MAX_CALL_DEPTH)))));
+
+        let result = interpret("fn f(n) { return f(n + 1); } f(0);", Interpreter::new().with_max_call_depth(10));
+        assert!(matches!(result, Err(ScriptError::RuntimeError(RuntimeError::StackOverflow(10)))));
+
+        assert_eq!(run("fn fact(n) { if (n <= 1) return 1; return n * fact(n - 1); } print fact(10);"), "3628800\n");
+
+        // The depth is back to zero once the calls unwind, also after an error
+        let mut interpreter = Interpreter::new().with_max_call_depth(10);
+        let program = Parser::new("fn f(n) { if (n > 0) return f(n - 1); return n; } f(9); f(9);".tokens().unwrap()).parse_program().unwrap();
+        assert!(interpreter.interpret(program).is_ok());
+        let program = Parser::new("f(20);".tokens().unwrap()).parse_program().unwrap();
+        assert!(interpreter.interpret(program).is_err());
+        let program = Parser::new("f(9);".tokens().unwrap()).parse_program().unwrap();
+        assert!(interpreter.interpret(program).is_ok());
+    }
+
+    #[test]
+    fn call_errors() {
+        let program = Parser::new("fn f(a) {} f(1, 2);".tokens().unwrap()).parse_program().unwrap();
+        let err = Interpreter::new().interpret(program).unwrap_err();
+        assert!(matches!(err, ScriptError::RuntimeError(RuntimeError::ArityMismatch { expected: 1, found: 2 })));
+
+        assert!(matches!(evaluate("\"f\"()"), Err(ScriptError::RuntimeError(RuntimeError::NotCallable))));
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(evaluate("\"hi\"").unwrap(), Value::Str(String::from("hi")));
+        assert_eq!(evaluate("true").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("null").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn unary() {
+        assert_eq!(evaluate("-3").unwrap(), Value::Integer(-3));
+        assert_eq!(evaluate("- -3.5").unwrap(), Value::Number(3.5f64));
+        assert_eq!(evaluate("!true").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("!null").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("!0").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("!!\"hi\"").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("not false").unwrap(), Value::Bool(true));
+        assert!(matches!(evaluate("-\"hi\""), Err(ScriptError::RuntimeError(RuntimeError::OperandMustBeNumber(_)))));
+    }
+
+    #[test]
+    fn negate_non_number() {
+        assert!(matches!(evaluate("-\"x\""), Err(ScriptError::RuntimeError(RuntimeError::OperandMustBeNumber(_)))));
+        assert!(matches!(evaluate("2 * true"), Err(ScriptError::RuntimeError(RuntimeError::OperandMustBeNumber(_)))));
+    }
+}
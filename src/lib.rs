@@ -0,0 +1,245 @@
+pub mod ast;
+pub mod context;
+pub mod diagnostic;
+pub mod environment;
+pub mod errors;
+pub mod interpreter;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod semantic;
+pub mod symbol;
+pub mod token;
+pub mod value;
+
+pub use context::Context;
+pub use diagnostic::{diagnostics, Diagnostic, Severity};
+pub use errors::{ParserError, RuntimeError, ScannerError, ScriptError};
+pub use interpreter::Interpreter;
+pub use parser::Parser;
+pub use resolver::Resolver;
+pub use scanner::{Scannable, TokenStream};
+pub use value::Value;
+
+/// Scan, parse and evaluate a single expression
+pub fn eval(source: &str) -> Result<Value, ScriptError> {
+    Interpreter::new().evaluate(&parse_expression(source)?)
+}
+
+/// Scan and parse a single expression, without evaluating it. Code left
+/// after the expression is an error
+pub fn parse_expression(source: &str) -> Result<ast::Expression, ScriptError> {
+    let mut parser = Parser::new(source.tokens()?);
+    let expression = parser.parse()?;
+
+    match parser.tokens.get(parser.current) {
+        Some(rest) if rest.token != token::Token::Eof => {
+            Err(parser.unexpected(rest).into())
+        },
+        _ => Ok(expression),
+    }
+}
+
+/// Scan and parse a script into its program, without running anything
+pub fn compile(source: &str) -> Result<Vec<ast::Statement>, ScriptError> {
+    let tokens = source.tokens()?;
+
+    Ok(Parser::new(tokens).parse_program()?)
+}
+
+/// Run a script in the given context, handing the context back for the
+/// next script to run in
+pub fn run(script: &str, mut context: Context) -> Result<Context, ScriptError> {
+    execute(script, &mut context)?;
+
+    Ok(context)
+}
+
+/// Scan, parse and interpret a script in the given context
+pub fn execute(script: &str, context: &mut Context) -> Result<(), ScriptError> {
+    // A bare exit command, same as exit(0)
+    if script.trim() == "exit" {
+        context.should_exit = true;
+        return Ok(());
+    }
+
+    let mut stream = TokenStream::new(script);
+    if let Some(source) = &context.source {
+        stream = stream.named(source);
+    }
+    let tokens = stream.collect::<Result<_, _>>()?;
+
+    let mut parser = Parser::new(tokens).with_max_nodes(context.max_nodes).with_echo(context.repl_echo);
+    let program = parser.parse_program()?;
+    Resolver::new(context.interpreter.names()).resolve(&program)?;
+
+    match context.interpreter.interpret(program) {
+        Err(ScriptError::Exit(code)) => {
+            context.should_exit = true;
+            context.exit_code = code;
+            Ok(())
+        },
+        result => result,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::tests::SharedBuffer;
+
+    #[test]
+    fn evaluate() {
+        assert_eq!(eval("2 * (4 - 1)").unwrap(), Value::Number(6f64));
+        assert!(matches!(eval("1 +"), Err(ScriptError::ParserError(_))));
+    }
+
+    #[test]
+    fn parse_single_expression() {
+        let expression = parse_expression("1 + x * 2").unwrap();
+        assert_eq!(ast::AstPrinter::new().print(expression).unwrap(), "(+ 1 (* x 2))");
+
+        assert!(matches!(parse_expression("1 +"), Err(ScriptError::ParserError(_))));
+        assert!(matches!(parse_expression("1 @ 2"), Err(ScriptError::ScannerError(_))));
+        assert!(matches!(parse_expression("1 2"), Err(ScriptError::ParserError(ParserError::Unexpected { .. }))));
+    }
+
+    #[test]
+    fn compile_program() {
+        let program = compile("let x = 1;\nfn f(a) { return a; }\nprint f(x);").unwrap();
+        assert_eq!(program.len(), 3);
+
+        assert!(matches!(compile("print 1 +;"), Err(ScriptError::ParserErrors(_))));
+        assert!(matches!(compile("print @;"), Err(ScriptError::ScannerError(_))));
+    }
+
+    #[test]
+    fn run_script() {
+        let buffer = SharedBuffer::default();
+        let context = run("print 1 + 2;", Context::with_output(Box::new(buffer.clone()))).unwrap();
+
+        assert_eq!(buffer.contents(), "3\n");
+        assert!(!context.should_exit);
+    }
+
+    #[test]
+    fn persistent_state() {
+        let buffer = SharedBuffer::default();
+        let context = run("let x = 10;", Context::with_output(Box::new(buffer.clone()))).unwrap();
+        run("print x;", context).unwrap();
+
+        assert_eq!(buffer.contents(), "10\n");
+    }
+
+    #[test]
+    fn exit() {
+        let context = run("exit", Context::new()).unwrap();
+        assert!(context.should_exit);
+        assert_eq!(context.exit_code, 0);
+
+        let buffer = SharedBuffer::default();
+        let context = run("print 1; exit(3); print 2;", Context::with_output(Box::new(buffer.clone()))).unwrap();
+        assert!(context.should_exit);
+        assert_eq!(context.exit_code, 3);
+        assert_eq!(buffer.contents(), "1\n");
+
+        assert!(matches!(run("exit(\"no\");", Context::new()), Err(ScriptError::RuntimeError(RuntimeError::InvalidArgument(_)))));
+    }
+
+    #[test]
+    fn incomplete_input() {
+        let buffer = SharedBuffer::default();
+        let mut context = Context::with_output(Box::new(buffer.clone()));
+
+        // The first line alone ends in the middle of the block
+        let mut input = String::from("if (true) {\n");
+        assert!(execute(&input, &mut context).is_err_and(|err| err.is_incomplete()));
+
+        input.push_str("print 1; }\n");
+        execute(&input, &mut context).unwrap();
+        assert_eq!(buffer.contents(), "1\n");
+
+        assert!(execute("print 1", &mut context).is_err_and(|err| !err.is_incomplete()));
+        assert!(execute("print (1 +", &mut context).is_err_and(|err| err.is_incomplete()));
+        assert!(execute("print ); {", &mut context).is_err_and(|err| !err.is_incomplete()));
+    }
+
+    #[test]
+    fn run_errors() {
+        assert!(matches!(run("print 1 +;", Context::new()), Err(ScriptError::ParserErrors(_))));
+        assert!(matches!(run("print -\"x\";", Context::new()), Err(ScriptError::RuntimeError(_))));
+
+        // Undeclared variables are found before anything runs
+        let buffer = SharedBuffer::default();
+        let result = run("print 1; print x;", Context::with_output(Box::new(buffer.clone())));
+        assert!(matches!(result, Err(ScriptError::ParserErrors(errors)) if matches!(errors[..], [ParserError::UndeclaredVariable { .. }])));
+        assert_eq!(buffer.contents(), "");
+    }
+
+    #[test]
+    fn resolve_against_globals() {
+        let context = run("let x = 1;", Context::new()).unwrap();
+        assert!(run("print x + clock();", context).is_ok());
+
+        let buffer = SharedBuffer::default();
+        run("{ fn f() { return z; } let z = 1; print f(); }", Context::with_output(Box::new(buffer.clone()))).unwrap();
+        assert_eq!(buffer.contents(), "1\n");
+    }
+
+    #[test]
+    fn repl_echo() {
+        let buffer = SharedBuffer::default();
+        let mut context = Context::with_output(Box::new(buffer.clone()));
+        context.repl_echo = true;
+
+        execute("1 + 2", &mut context).unwrap();
+        execute("1 + 2;", &mut context).unwrap();
+        execute("let x = 4; x", &mut context).unwrap();
+        assert_eq!(buffer.contents(), "3\n4\n");
+
+        let buffer = SharedBuffer::default();
+        let mut context = Context::with_output(Box::new(buffer.clone()));
+        assert!(execute("1 + 2", &mut context).is_err());
+        execute("1 + 2;", &mut context).unwrap();
+        assert_eq!(buffer.contents(), "");
+    }
+
+    #[test]
+    fn include() {
+        let directory = std::env::temp_dir().join(format!("lhscript-include-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("lib")).unwrap();
+        std::fs::write(directory.join("a.lhscript"), "include \"lib/b.lhscript\";\nprint a;").unwrap();
+        std::fs::write(directory.join("lib/b.lhscript"), "let a = 1;").unwrap();
+        std::fs::write(directory.join("c.lhscript"), "include \"d.lhscript\";").unwrap();
+        std::fs::write(directory.join("d.lhscript"), "include \"c.lhscript\";").unwrap();
+
+        let run_file = |name: &str, context: &mut Context| {
+            let path = directory.join(name);
+            context.interpreter.set_file(Some(&path));
+            execute(&std::fs::read_to_string(&path).unwrap(), context)
+        };
+
+        let buffer = SharedBuffer::default();
+        let mut context = Context::with_output(Box::new(buffer.clone()));
+        run_file("a.lhscript", &mut context).unwrap();
+        assert_eq!(buffer.contents(), "1\n");
+
+        let result = run_file("c.lhscript", &mut context);
+        assert!(matches!(result, Err(ScriptError::RuntimeError(RuntimeError::CircularInclude { .. }))));
+
+        let result = execute("include \"missing.lhscript\";", &mut context);
+        assert!(matches!(result, Err(ScriptError::FileIo(err)) if err.kind() == std::io::ErrorKind::NotFound));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn named_source() {
+        let mut context = Context::new();
+        context.source = Some(String::from("script.lhscript"));
+
+        let err = execute("let x = 1;\nprint x < \"a\";", &mut context).unwrap_err();
+        assert_eq!(err.to_string(), "error[E0203]: Operands must be two numbers or two strings at script.lhscript:2:9");
+    }
+}
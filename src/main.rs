@@ -1,27 +1,13 @@
 use clap::Parser;
+use lhscript::{ast::AstPrinter, diagnostics, errors::explain, execute, run, Context, Scannable, ScriptError};
 use std::{
     fs::read_to_string,
-    io::{stdin, BufRead},
+    io::{read_to_string as read_all, stdin, stdout, BufRead, Write},
     path::PathBuf,
 };
 
-mod context;
-use context::Context;
-
-mod errors;
-use errors::ScriptError;
-
-mod scanner;
-use scanner::*;
-
-mod token;
-use token::*;
-
-mod ast;
-use ast::*;
-
-mod parser;
-
+/// REPL command listing the variables, a leading ':' can never start a statement
+const ENV_COMMAND: &str = ":env";
 
 #[derive(Debug, clap::Parser)]
 #[command(author, version, about, long_about=None)]
@@ -29,81 +15,207 @@ struct Args {
     /// Filename to run
     file: Option<String>,
 
-    /// Run in interactive mode after executing file
+    /// Code to run instead of a file
+    #[arg(short, long, value_name = "CODE", conflicts_with = "file")]
+    eval: Option<String>,
+
+    /// Run in interactive mode after executing file or code
     #[arg(short, long)]
     interactive: bool,
+
+    /// Explain an error code, e.g. E0001, and exit
+    #[arg(long, value_name = "CODE")]
+    explain: Option<String>,
+
+    /// Maximum number of expression nodes a script may contain
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<usize>,
+
+    /// Print the tokens of the file or code, or of stdin, and exit
+    #[arg(long, conflicts_with = "ast")]
+    tokens: bool,
+
+    /// Print the syntax tree of the file or code, or of stdin, and exit
+    #[arg(long, conflicts_with = "diagnostics")]
+    ast: bool,
+
+    /// Print every error and warning in the file or code, or in stdin, as
+    /// JSON lines and exit
+    #[arg(long, conflicts_with = "tokens")]
+    diagnostics: bool,
+}
+
+/// What to do, as decided by the arguments
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Explain(String),
+    Tokens,
+    Ast,
+    Diagnostics,
+    Eval(String),
+    Run,
+}
+
+impl Args {
+    fn mode(&self) -> Mode {
+        if let Some(code) = &self.explain {
+            Mode::Explain(code.clone())
+        } else if self.tokens {
+            Mode::Tokens
+        } else if self.ast {
+            Mode::Ast
+        } else if self.diagnostics {
+            Mode::Diagnostics
+        } else if let Some(code) = &self.eval {
+            Mode::Eval(code.clone())
+        } else {
+            Mode::Run
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
 
+    match args.mode() {
+        Mode::Explain(code) => {
+            match explain(&code) {
+                Some(explanation) => println!("{}", explanation),
+                None => {
+                    eprintln!("Unknown error code {}", code);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        },
+        Mode::Tokens | Mode::Ast | Mode::Diagnostics => {
+            if let Err(err) = dump(&args) {
+                std::process::exit(err.exit_code());
+            }
+            return;
+        },
+        Mode::Eval(_) | Mode::Run => {},
+    }
 
-        // Temporary
-        let expr = Expression::Binary(BinaryExpression {
-            left: Box::new(Expression::Unary(UnaryExpression {
-                operator: Token::Minus,
-                right: Box::new(Expression::Literal(LiteralExpression { 
-                    literal: Token::Number(123f64) 
-                })),
-            })),
-            operator: Token::Star,
-            right: Box::new(Expression::Grouping(GroupingExpression { 
-                group: Box::new(Expression::Literal(LiteralExpression { 
-                    literal: Token::Number(45.67f64),
-                })),
-            })),
-        });
-    
-        let printer = AstPrinter;
-        let exp = printer.print(expr).unwrap();
-        println!("AST-test: {}", exp);
-    
-        let tokens = "1 * (2 + 3)".tokens().unwrap();
-        println!("Tokens: {:?}", tokens);
-        let mut parser = parser::Parser::new(tokens);
-        let expr = parser.parse().unwrap();
-        println!("Expression: {:?}", expr);
-        let exp = printer.print(expr).unwrap();
-        println!("AST-printer: {}", exp);
+    let mut context = Context::new();
+    context.max_nodes = args.max_nodes;
+
+    let ran_script = match (args.mode(), args.file) {
+        (Mode::Eval(code), _) => {
+            context = run_eval(&code, context).unwrap_or_else(|err| std::process::exit(err.exit_code()));
+            true
+        },
+        (_, Some(file)) => {
+            context = run_file(PathBuf::from(file), context).unwrap_or_else(|err| std::process::exit(err.exit_code()));
+            true
+        },
+        _ => false,
+    };
+
+    if !ran_script || (args.interactive && !context.should_exit) {
+        context = run_prompt(context).unwrap_or_else(|err| std::process::exit(err.exit_code()));
+    }
 
+    std::process::exit(context.exit_code);
+}
 
-    let mut context = Context::new();
+/// Print the tokens, the syntax tree or the diagnostics of the code or file,
+/// or of stdin if there is neither, errors are reported on stderr
+fn dump(args: &Args) -> Result<(), ScriptError> {
+    let script = match (&args.eval, &args.file) {
+        (Some(code), _) => Ok(code.clone()),
+        (None, Some(file)) => read_to_string(file),
+        (None, None) => read_all(stdin()),
+    }.inspect_err(|err| eprintln!("{}", err))?;
 
-    if let Some(file) = args.file {
-        println!("Running file {}, interactive={}", file, args.interactive);
-        context = run_file(PathBuf::from(file), context).expect("Error");
+    let output = dump_script(&script, &args.mode(), args.max_nodes).inspect_err(|err| eprintln!("{}", err.diagnostic(&script, None)))?;
+    println!("{}", output);
 
-        if args.interactive {
-            _ = run_prompt(context)
-        }
-    } else {
-        println!("Running prompt:");
-        _ = run_prompt(context).expect("Error");
-    }
+    Ok(())
+}
 
+fn dump_script(script: &str, mode: &Mode, max_nodes: Option<usize>) -> Result<String, ScriptError> {
+    // Problems in the script are the output here, not a failure
+    if *mode == Mode::Diagnostics {
+        let lines: Vec<String> = diagnostics(script).iter().map(|diagnostic| diagnostic.to_json()).collect();
+        return Ok(lines.join("\n"));
+    }
 
+    let tokens = script.tokens()?;
 
+    if *mode == Mode::Tokens {
+        let lines: Vec<String> = tokens.iter()
+            .map(|token| format!("{:#} {}", token.position, token.token))
+            .collect();
+        return Ok(lines.join("\n"));
+    }
 
+    let program = lhscript::Parser::new(tokens).with_max_nodes(max_nodes).parse_program()?;
+    AstPrinter::new().print_program(&program)
 }
 
+/// Run a script file, errors are reported on stderr
 fn run_file(path: PathBuf, mut context: Context) -> Result<Context, ScriptError> {
-    let script = read_to_string(path)?;
+    let script = read_to_string(&path).inspect_err(|err| eprintln!("{}", err))?;
+    let name = path.display().to_string();
+    context.source = Some(name.clone());
+    context.interpreter.set_file(Some(&path));
 
-    context = run(&script, context)?;
+    context = run(&script, context).inspect_err(|err| eprintln!("{}", err.diagnostic(&script, Some(&name))))?;
 
     Ok(context)
 }
 
+/// Run code given on the command line, errors are reported on stderr
+fn run_eval(code: &str, mut context: Context) -> Result<Context, ScriptError> {
+    context.source = Some(String::from("<eval>"));
+
+    run(code, context).inspect_err(|err| eprintln!("{}", err.diagnostic(code, Some("<eval>"))))
+}
+
+/// Run lines from stdin until exit or end of input, errors on a line are
+/// reported on stderr. Only fails on IO errors
 fn run_prompt(mut context: Context) -> Result<Context, ScriptError> {
+    context.source = Some(String::from("<repl>"));
+    context.repl_echo = true;
+
     let mut buffer = String::new();
     let mut stdin = stdin().lock();
+    let io_error = |err: std::io::Error| {
+        eprintln!("{}", err);
+        ScriptError::from(err)
+    };
 
     loop {
-        print!(">>> ");
+        // Continuation lines get their own prompt
+        print!("{}", if buffer.is_empty() { ">>> " } else { "... " });
+        stdout().flush().map_err(io_error)?;
+
+        if stdin.read_line(&mut buffer).map_err(io_error)? == 0 {
+            // Ctrl-D, end of input
+            println!();
+            break;
+        }
 
-        stdin.read_line(&mut buffer)?;
+        if buffer.trim() == ENV_COMMAND {
+            let variables = environment(&context);
+            if !variables.is_empty() {
+                println!("{}", variables);
+            }
+            buffer.clear();
+            continue;
+        }
 
-        context = run(&buffer, context)?;
+        match execute(&buffer, &mut context) {
+            Ok(()) => {},
+            // Keep reading lines until the statement is complete
+            Err(err) if err.is_incomplete() => continue,
+            // A bad line is reported, but shouldn't end the session
+            Err(err) => eprintln!("{}", err.diagnostic(&buffer, context.source.as_deref())),
+        }
+
+        // Only new lines should be run from here on
+        buffer.clear();
 
         if context.should_exit {
             break;
@@ -113,16 +225,64 @@ fn run_prompt(mut context: Context) -> Result<Context, ScriptError> {
     Ok(context)
 }
 
-fn run(script: &str, mut context: Context) -> Result<Context, ScriptError> {
-    // TODO: parse
+/// Every variable defined in the context as `name = value`, one per line
+fn environment(context: &Context) -> String {
+    let lines: Vec<String> = context.interpreter.variables().iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect();
 
-    let tokens = script.tokens()?;
+    lines.join("\n")
+}
 
-    for (index, token) in tokens.into_iter().enumerate() {
-        println!("{}: {:?}", index, token);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(args: &[&str]) -> Mode {
+        Args::try_parse_from([&["lhscript"], args].concat()).unwrap().mode()
     }
 
-    context.should_exit = true;
+    #[test]
+    fn modes() {
+        assert_eq!(mode(&[]), Mode::Run);
+        assert_eq!(mode(&["script.lhs", "-i"]), Mode::Run);
+        assert_eq!(mode(&["--tokens"]), Mode::Tokens);
+        assert_eq!(mode(&["--ast", "script.lhs"]), Mode::Ast);
+        assert_eq!(mode(&["--diagnostics", "script.lhs"]), Mode::Diagnostics);
+        assert_eq!(mode(&["--explain", "E0001", "--ast"]), Mode::Explain(String::from("E0001")));
+
+        assert!(Args::try_parse_from(["lhscript", "--tokens", "--ast"]).is_err());
+        assert!(Args::try_parse_from(["lhscript", "--ast", "--diagnostics"]).is_err());
+    }
 
-    Ok(context)
+    #[test]
+    fn eval_mode() {
+        assert_eq!(mode(&["-e", "print 1;"]), Mode::Eval(String::from("print 1;")));
+        assert_eq!(mode(&["--eval", "print 1;", "-i"]), Mode::Eval(String::from("print 1;")));
+        assert_eq!(mode(&["-e", "print 1;", "--ast"]), Mode::Ast);
+
+        assert!(Args::try_parse_from(["lhscript", "-e", "print 1;", "script.lhs"]).is_err());
+    }
+
+    #[test]
+    fn dump() {
+        assert_eq!(dump_script("print 1 + 2;", &Mode::Ast, None).unwrap(), "(print (+ 1 2))");
+
+        let tokens = dump_script("x;", &Mode::Tokens, None).unwrap();
+        assert_eq!(tokens, "0001:001 x\n0001:002 ;\n0001:003 end of file");
+
+        let diagnostics = dump_script("print 1 @;\nlet;", &Mode::Diagnostics, None).unwrap();
+        assert_eq!(diagnostics.lines().count(), 2);
+        assert!(diagnostics.starts_with(r#"{"message":"Unexpected token at 1:9","line":1,"column":9,"severity":"error"}"#));
+    }
+
+    #[test]
+    fn environment_listing() {
+        let mut context = Context::new();
+        assert_eq!(environment(&context), "");
+
+        execute("let x = 1; let name = \"lhscript\";", &mut context).unwrap();
+        assert_eq!(environment(&context), "name = lhscript\nx = 1");
+    }
 }
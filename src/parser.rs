@@ -1,22 +1,108 @@
-use crate::{token::{TokenMetadata, Token}, errors::ParserError, ast::{Expression, BinaryExpression, UnaryExpression, LiteralExpression, GroupingExpression}};
+use crate::{token::{TokenMetadata, Token, TemplatePart}, errors::{ParserError, Warning}, scanner::{Keywords, Position, default_keywords, suggest_keyword}, ast::{Expression, BinaryExpression, UnaryExpression, LiteralExpression, GroupingExpression, VariableExpression, LogicalExpression, CoalesceExpression, AssignExpression, CallExpression, ArrayExpression, IndexExpression, GetExpression, MapExpression, LambdaExpression, IncrementExpression, BlockExpression, InterpolationExpression, Statement}};
 
 /*
 GRAMMAR
 
-expression     → equality ;
+program        → declaration* EOF ;
+declaration    → fnDecl
+               | letDecl
+               | statement ;
+fnDecl         → "fn" IDENTIFIER "(" parameters? ")" block ;
+parameters     → IDENTIFIER ( "," IDENTIFIER )* ","? ;
+letDecl        → "let" binding ( "," binding )* ";" ;
+binding        → IDENTIFIER ( "=" expression )? ;
+statement      → exprStmt
+               | block
+               | forStmt
+               | breakStmt
+               | continueStmt
+               | ifStmt
+               | printStmt
+               | assertStmt
+               | includeStmt
+               | returnStmt
+               | whileStmt ;
+exprStmt       → expression ";" ;                    // ";" optional last in the REPL
+forStmt        → "for" "(" ( letDecl | exprStmt | ";" )
+                 expression? ";"
+                 expression? ")" statement ;
+ifStmt         → "if" "(" expression ")" statement
+               ( "else" statement )? ;
+returnStmt     → "return" expression? ";" ;
+breakStmt      → "break" ";" ;
+continueStmt   → "continue" ";" ;
+whileStmt      → "while" "(" expression ")" statement ;
+block          → "{" declaration* "}" ;
+printStmt      → "print" ( expression ( "," expression )* )? ";" ;
+assertStmt     → "assert" "(" expression ( "," expression )? ")" ";" ;
+includeStmt    → "include" STRING ";" ;
+
+expression     → assignment ;
+assignment     → IDENTIFIER "=" assignment
+               | pipeline ;
+pipeline       → coalesce ( "|>" coalesce )* ;
+coalesce       → logic_or ( "??" logic_or )* ;
+logic_or       → logic_and ( "||" logic_and )* ;
+logic_and      → equality ( "&&" equality )* ;
 equality       → comparison ( ( "!=" | "==" ) comparison )* ;
-comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;   // chained, 1 < x < 3
 term           → factor ( ( "-" | "+" ) factor )* ;
-factor         → unary ( ( "/" | "*" ) unary )* ;
+factor         → power ( ( "/" | "*" | "%" ) power )* ;
+power          → unary ( "**" power )? ;
 unary          → ( "!" | "-" ) unary
-               | primary ;
-primary        → NUMBER | STRING | "true" | "false" | "nil"
-               | "(" expression ")" ;
+               | call ;
+call           → primary ( "(" arguments? ")" | "[" expression "]" | "." IDENTIFIER | "++" | "--" )* ;
+arguments      → expression ( "," expression )* ","? ;
+primary        → NUMBER | STRING | TEMPLATE | "true" | "false" | "nil"
+               | "(" expression ")"
+               | "(" parameters? ")" "->" expression
+               | "[" arguments? "]"
+               | "{" ( entry ( "," entry )* ","? )? "}"
+               | "{" declaration* expression? "}"      // a block yielding its last expression
+               | IDENTIFIER ;
+entry          → ( STRING | NUMBER | "true" | "false" ) ":" expression ;
+
+A TEMPLATE is a string embedding expressions, "x is ${x}", each parsed from
+its own tokens by a nested parser
 */
 
+/// Maximum number of parameters in a function declaration
+const MAX_PARAMETERS: usize = 255;
+
+/// Default limit of nesting, counting each subexpression, block, function
+/// body and body of an if, while or for as a level. Each level takes a dozen
+/// stack frames so this keeps well clear of overflowing even a small stack
+const MAX_DEPTH: usize = 64;
+
+/// Parser of the operands of a binary operator
+type Operand = fn(&mut Parser) -> Result<Expression, ParserError>;
+
+/// Builder of a binary expression out of its operands and operator
+type Build = fn(Box<Expression>, TokenMetadata, Box<Expression>) -> Expression;
+
 pub struct Parser {
     pub tokens: Vec<TokenMetadata>,
     pub current: usize,
+    pub warnings: Vec<Warning>,
+
+    /// Number of expression nodes produced so far
+    nodes: usize,
+
+    /// Upper limit of expression nodes, if any
+    max_nodes: Option<usize>,
+
+    /// Current nesting of the expression or statement being parsed, see [MAX_DEPTH]
+    depth: usize,
+
+    /// Upper limit of the nesting
+    max_depth: usize,
+
+    /// Take a last expression without a semicolon to print its value, like
+    /// in the REPL
+    echo: bool,
+
+    /// Keywords the tokens were scanned with, the default ones if not given
+    keywords: Option<Keywords>,
 }
 
 impl Parser {
@@ -24,13 +110,101 @@ impl Parser {
     pub fn new(tokens: Vec<TokenMetadata>) -> Self {
         Parser { 
             tokens, 
-            current: 0 
+            current: 0,
+            warnings: vec![],
+            nodes: 0,
+            max_nodes: None,
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            echo: false,
+            keywords: None,
+        }
+    }
+
+    /// Start over on new tokens, reusing the allocation of the token buffer.
+    /// The node budget and warnings start over as well
+    pub fn reset(&mut self, tokens: Vec<TokenMetadata>) {
+        self.tokens.clear();
+        self.tokens.extend(tokens);
+        self.current = 0;
+        self.warnings.clear();
+        self.nodes = 0;
+        self.depth = 0;
+    }
+
+    /// Limit the total number of expression nodes the parser may produce
+    pub fn with_max_nodes(mut self, max_nodes: Option<usize>) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Limit how deeply expressions, blocks, function bodies and the bodies
+    /// of if, while and for statements may be nested, 64 levels by default
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Print the value of a last expression lacking its semicolon
+    pub fn with_echo(mut self, echo: bool) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    /// Suggest keywords for misspelled ones out of the keywords the tokens
+    /// were scanned with, see [crate::scanner::TokenStream::with_keywords]
+    pub fn with_keywords(mut self, keywords: Keywords) -> Self {
+        self.keywords = Some(keywords);
+        self
+    }
+
+    /// The keyword an identifier token is likely a misspelling of, if any
+    fn suggestion(&self, token: &Token) -> Option<String> {
+        let Token::Identifier(name) = token else {
+            return None;
+        };
+
+        match &self.keywords {
+            Some(keywords) => suggest_keyword(name.as_str(), keywords),
+            None => suggest_keyword(name.as_str(), &default_keywords()),
+        }
+    }
+
+    /// Error for a token found where it can't go
+    pub fn unexpected(&self, found: &TokenMetadata) -> ParserError {
+        ParserError::Unexpected {
+            found: found.token.clone(),
+            position: found.position,
+            suggestion: self.suggestion(&found.token),
+        }
+    }
+
+    /// Parse something one level deeper, failing rather than risking a stack
+    /// overflow once the nesting is too deep
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, ParserError>) -> Result<T, ParserError> {
+        if self.depth >= self.max_depth {
+            return Err(ParserError::TooDeep(self.max_depth, self.position()));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Count a newly produced expression node against the node budget
+    fn node(&mut self, expression: Expression) -> Result<Expression, ParserError> {
+        self.nodes += 1;
+        match self.max_nodes {
+            Some(max) if self.nodes > max => Err(ParserError::TooManyNodes(max, self.position())),
+            _ => Ok(expression),
         }
     }
 
-    /// Check is parser is at end of file
+    /// Check is parser is at end of file, running out of tokens counts as
+    /// the end as well in case the stream lacks its Eof token
     fn is_at_end(&self) -> bool {
-        self.peek().is_some_and(|t| t.token == Token::Eof)
+        self.peek().is_none_or(|t| t.token == Token::Eof)
     }
 
     /// Check if parsers current position is on a specific token
@@ -61,9 +235,31 @@ impl Parser {
         self.previous()
     }
 
+    /// Get the token just consumed, typically the operator after a call to matches
+    fn previous_token(&self) -> Result<TokenMetadata, ParserError> {
+        self.previous()
+            .cloned()
+            .ok_or(ParserError::UnexpectedEof(self.position()))
+    }
+
     /// Peek at the character on the current pointer position
     fn peek(&self) -> Option<&TokenMetadata> {
-        self.tokens.get(self.current)
+        self.peek_at(0)
+    }
+
+    /// Peek at the token offset steps ahead of the current position, without
+    /// moving forward
+    fn peek_at(&self, offset: usize) -> Option<&TokenMetadata> {
+        self.tokens.get(self.current + offset)
+    }
+
+    /// Position of the current token, or of the last one when past the end,
+    /// to report errors at
+    fn position(&self) -> Position {
+        self.peek()
+            .or(self.tokens.last())
+            .map(|t| t.position)
+            .unwrap_or_default()
     }
 
     /// Get the token at the previous pointer position
@@ -79,107 +275,549 @@ impl Parser {
     fn consume(&mut self, token: &Token) -> Result<(), ParserError> {
         if self.check(token) {
             self.advance();
-            Ok(())
-        } else {
-            Err(ParserError::Consume)
+            return Ok(());
+        }
+
+        match self.peek() {
+            Some(found) => Err(ParserError::Consume {
+                expected: token.clone(),
+                found: found.token.clone(),
+                position: found.position,
+                suggestion: self.suggestion(&found.token),
+            }),
+            None => Err(ParserError::UnexpectedEof(self.position())),
+        }
+    }
+
+    /// Consume an identifier at the current position and return its name
+    fn identifier(&mut self) -> Result<String, ParserError> {
+        match self.peek().map(|t| &t.token) {
+            Some(Token::Identifier(name)) => {
+                let name = name.to_string();
+                self.advance();
+                Ok(name)
+            },
+            _ => Err(ParserError::ExpectedIdentifier(self.position())),
+        }
+    }
+
+    /// Consume a number literal at the current position and return its token
+    fn consume_number(&mut self) -> Option<Token> {
+        match self.peek().map(|t| &t.token) {
+            Some(number @ (Token::Number(_) | Token::Integer(_))) => {
+                let number = number.clone();
+                self.advance();
+                Some(number)
+            },
+            _ => None,
+        }
+    }
+
+    /// Consume a literal value at the current position and return its token
+    fn literal(&mut self) -> Option<Token> {
+        if self.matches(&[Token::False, Token::True, Token::Null]) {
+            return self.previous().map(|t| t.token.clone());
+        }
+        if let Some(s) = self.consume_string() {
+            return Some(Token::String(s));
+        }
+        self.consume_number()
+    }
+
+    /// Consume a string literal at the current position and return its value
+    fn consume_string(&mut self) -> Option<String> {
+        match self.peek().map(|t| &t.token) {
+            Some(Token::String(s)) => {
+                let s = s.clone();
+                self.advance();
+                Some(s)
+            },
+            _ => None,
         }
     }
 
-    /// Synchronize to next statement
-    // fn synchronize(&mut self) {
-    //     self.advance();
+    /// Skip tokens until the next statement boundary, used to recover after
+    /// an error so that several errors can be reported in one go
+    fn synchronize(&mut self) {
+        if !self.is_at_end() {
+            self.advance();
+        }
+
+        while !self.is_at_end() {
+            if self.previous().is_some_and(|t| t.token == Token::Semicolon) {
+                return;
+            }
 
-    //     while !self.is_at_end() {
-    //         match self.peek().unwrap().token {
-    //             Token::Semicolon => break,
-    //             Token::Class => break,
-    //             Token::Fn => break,
-    //             Token::Let => break,
-    //             Token::For => break,
-    //             Token::If => break,
-    //             Token::While => break,
-    //             Token::Print => break,
-    //             Token::Return => break,
-    //             _ => {}
-    //         }
+            match self.peek().map(|t| &t.token) {
+                Some(Token::Class) => return,
+                Some(Token::Fn) => return,
+                Some(Token::Let) => return,
+                Some(Token::For) => return,
+                Some(Token::If) => return,
+                Some(Token::While) => return,
+                Some(Token::Print) => return,
+                Some(Token::Assert) => return,
+                Some(Token::Include) => return,
+                Some(Token::Return) => return,
+                _ => {}
+            }
 
-    //         self.advance();
-    //     }
-    // }
+            self.advance();
+        }
+    }
 
     /// Parse the next expression
     pub fn parse(&mut self) -> Result<Expression,ParserError> {
         self.expression()
     }
 
+    /// Parse all statements until end of file. On errors the parser skips
+    /// to the next statement and carries on, returning every error found
+    pub fn parse_program(&mut self) -> Result<Vec<Statement>, Vec<ParserError>> {
+        let mut statements = vec![];
+        let mut errors = vec![];
+
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(declaration) => statements.extend(declaration),
+                // The budget is exhausted, any further statement would fail as well
+                Err(error @ ParserError::TooManyNodes(..)) => {
+                    errors.push(error);
+                    break;
+                },
+                // The rest of the code is most likely nested as deeply, like
+                // the remaining bodies of a long chain of ifs
+                Err(error @ ParserError::TooDeep(..)) => {
+                    errors.push(error);
+                    break;
+                },
+                Err(error) => {
+                    errors.push(error);
+                    self.synchronize();
+                },
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parse a declaration or a statement on the current pointer. A let
+    /// declaring several variables results in one statement per variable
+    fn declaration(&mut self) -> Result<Vec<Statement>, ParserError> {
+        if self.matches(&[Token::Fn]) {
+            return Ok(vec![self.function_declaration()?]);
+        }
+        if self.matches(&[Token::Let]) {
+            return self.let_declaration();
+        }
+
+        Ok(vec![self.statement()?])
+    }
+
+    /// Parse a function declaration, the fn keyword is already consumed
+    fn function_declaration(&mut self) -> Result<Statement, ParserError> {
+        let name = self.identifier()?;
+
+        self.consume(&Token::LeftParenthesis)?;
+        let params = self.parameters()?;
+
+        self.consume(&Token::LeftBrace)?;
+        let body = self.nested(Self::block)?;
+
+        Ok(Statement::Function { name, params, body })
+    }
+
+    /// Parse a parameter list up to and including the closing parenthesis,
+    /// the opening one is already consumed
+    fn parameters(&mut self) -> Result<Vec<String>, ParserError> {
+        let mut params = vec![];
+        if !self.check(&Token::RightParenthesis) {
+            loop {
+                if params.len() >= MAX_PARAMETERS {
+                    return Err(ParserError::TooManyParameters(self.position()));
+                }
+                params.push(self.identifier()?);
+
+                // A trailing comma is allowed
+                if !self.matches(&[Token::Comma]) || self.check(&Token::RightParenthesis) {
+                    break;
+                }
+            }
+        }
+        self.consume(&Token::RightParenthesis)?;
+
+        Ok(params)
+    }
+
+    /// Whether the parenthesis at the current pointer opens the parameters
+    /// of a lambda rather than a grouping, i.e. it is closed by a ')' followed
+    /// by '->' with only names and commas in between
+    fn is_lambda(&self) -> bool {
+        let mut offset = 1;
+        while matches!(self.peek_at(offset), Some(TokenMetadata { token: Token::Identifier(_) | Token::Comma, .. })) {
+            offset += 1;
+        }
+
+        matches!(self.peek_at(offset), Some(TokenMetadata { token: Token::RightParenthesis, .. }))
+            && matches!(self.peek_at(offset + 1), Some(TokenMetadata { token: Token::Arrow, .. }))
+    }
+
+    /// Parse a declaration of one or more comma separated variables, the let
+    /// keyword is already consumed
+    fn let_declaration(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let mut declarations = vec![];
+        loop {
+            let name = self.identifier()?;
+
+            let initializer = if self.matches(&[Token::Equal]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            declarations.push(Statement::Let { name, initializer });
+
+            if !self.matches(&[Token::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+        Ok(declarations)
+    }
+
+    /// Parse a statement on the current pointer
+    fn statement(&mut self) -> Result<Statement, ParserError> {
+        if self.matches(&[Token::LeftBrace]) {
+            return Ok(Statement::Block(self.nested(Self::block)?));
+        }
+        if self.matches(&[Token::For]) {
+            return self.for_statement();
+        }
+        if self.matches(&[Token::If]) {
+            return self.if_statement();
+        }
+        if self.matches(&[Token::Print]) {
+            return self.print_statement();
+        }
+        if self.matches(&[Token::Assert]) {
+            return self.assert_statement();
+        }
+        if self.matches(&[Token::Include]) {
+            return self.include_statement();
+        }
+        if self.matches(&[Token::Return]) {
+            return self.return_statement();
+        }
+        if self.matches(&[Token::While]) {
+            return self.while_statement();
+        }
+        if self.matches(&[Token::Break]) {
+            self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+            return Ok(Statement::Break);
+        }
+        if self.matches(&[Token::Continue]) {
+            self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+            return Ok(Statement::Continue);
+        }
+
+        self.expression_statement()
+    }
+
+    /// Parse the statements of a block, the opening brace is already consumed.
+    /// Code after a return is warned about, once per block
+    fn block(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let mut statements = vec![];
+        let mut unreachable = false;
+
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            if !unreachable && matches!(statements.last(), Some(Statement::Return(_))) {
+                unreachable = true;
+                if let Some(token) = self.peek() {
+                    self.warnings.push(Warning::UnreachableCode(token.position));
+                }
+            }
+
+            statements.extend(self.declaration()?);
+        }
+
+        self.consume(&Token::RightBrace).map_err(|_| ParserError::UnterminatedBlock(self.position()))?;
+        Ok(statements)
+    }
+
+    /// Parse the parenthesized condition following a keyword, like if or while.
+    /// A bare assignment as condition is warned about, wrap it in an extra pair
+    /// of parentheses to silence the warning
+    fn condition(&mut self, keyword: &'static str) -> Result<Expression, ParserError> {
+        self.consume(&Token::LeftParenthesis).map_err(|_| ParserError::ExpectedLeftParenthesis(keyword, self.position()))?;
+        let position = self.peek().map(|t| t.position);
+        let condition = self.expression()?;
+        if let (Expression::Assign(_), Some(position)) = (&condition, position) {
+            self.warnings.push(Warning::AssignmentInCondition(position));
+        }
+        self.consume(&Token::RightParenthesis).map_err(|_| ParserError::ExpectedRightParenthesis(keyword, self.position()))?;
+        Ok(condition)
+    }
+
+    /// Parse an if statement, the if keyword is already consumed. An else is
+    /// always bound to the nearest preceding if
+    fn if_statement(&mut self) -> Result<Statement, ParserError> {
+        let condition = self.condition("if")?;
+        let then_branch = Box::new(self.nested(Self::statement)?);
+
+        let else_branch = if self.matches(&[Token::Else]) {
+            Some(Box::new(self.nested(Self::statement)?))
+        } else {
+            None
+        };
+
+        Ok(Statement::If { condition, then_branch, else_branch })
+    }
+
+    /// Parse a while loop, the while keyword is already consumed
+    fn while_statement(&mut self) -> Result<Statement, ParserError> {
+        let condition = self.condition("while")?;
+        let body = Box::new(self.nested(Self::statement)?);
+
+        Ok(Statement::While { condition, body, increment: None })
+    }
+
+    /// Parse a for loop, the for keyword is already consumed. The loop is
+    /// desugared into a while loop wrapped in a block, so no dedicated node is needed
+    fn for_statement(&mut self) -> Result<Statement, ParserError> {
+        self.consume(&Token::LeftParenthesis).map_err(|_| ParserError::ExpectedLeftParenthesis("for", self.position()))?;
+
+        let mut initializer = if self.matches(&[Token::Semicolon]) {
+            vec![]
+        } else if self.matches(&[Token::Let]) {
+            self.let_declaration()?
+        } else {
+            vec![self.expression_statement()?]
+        };
+
+        let condition = if self.check(&Token::Semicolon) {
+            Expression::Literal(LiteralExpression { literal: Token::True })
+        } else {
+            self.expression()?
+        };
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+
+        let increment = if self.check(&Token::RightParenthesis) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(&Token::RightParenthesis).map_err(|_| ParserError::ExpectedRightParenthesis("for", self.position()))?;
+
+        let body = self.nested(Self::statement)?;
+        let mut body = Statement::While { condition, body: Box::new(body), increment };
+
+        if !initializer.is_empty() {
+            initializer.push(body);
+            body = Statement::Block(initializer);
+        }
+
+        Ok(body)
+    }
+
+    /// Parse a return statement, the return keyword is already consumed
+    fn return_statement(&mut self) -> Result<Statement, ParserError> {
+        let value = if self.check(&Token::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+        Ok(Statement::Return(value))
+    }
+
+    /// Parse a print statement, the print keyword is already consumed
+    fn print_statement(&mut self) -> Result<Statement, ParserError> {
+        let mut expressions = vec![];
+        if !self.check(&Token::Semicolon) {
+            loop {
+                expressions.push(self.expression()?);
+                if !self.matches(&[Token::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+        Ok(Statement::Print(expressions))
+    }
+
+    /// Parse an assert statement, the assert keyword is already consumed
+    fn assert_statement(&mut self) -> Result<Statement, ParserError> {
+        self.consume(&Token::LeftParenthesis).map_err(|_| ParserError::ExpectedLeftParenthesis("assert", self.position()))?;
+
+        let position = self.peek().map(|t| t.position).unwrap_or_default();
+        let condition = self.expression()?;
+        let message = if self.matches(&[Token::Comma]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume(&Token::RightParenthesis).map_err(|_| ParserError::ExpectedRightParenthesis("assert", self.position()))?;
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+        Ok(Statement::Assert { condition, message, position })
+    }
+
+    /// Parse an include statement, the include keyword is already consumed
+    fn include_statement(&mut self) -> Result<Statement, ParserError> {
+        let position = self.peek().map(|t| t.position).unwrap_or_default();
+        let path = self.consume_string().ok_or(ParserError::ExpectedIncludePath(self.position()))?;
+
+        self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+        Ok(Statement::Include { path, position })
+    }
+
+    /// Parse an expression followed by a semicolon
+    fn expression_statement(&mut self) -> Result<Statement, ParserError> {
+        let start = self.current;
+        let expression = self.expression()?;
+        if self.echo && self.is_at_end() {
+            return Ok(Statement::Print(vec![expression]));
+        }
+
+        // A misspelled keyword starting a statement is read as an identifier,
+        // so the statement runs on past where a semicolon was expected
+        self.consume(&Token::Semicolon).map_err(|_| match self.tokens.get(start) {
+            Some(first) if self.suggestion(&first.token).is_some() => self.unexpected(first),
+            _ => ParserError::ExpectedSemicolon(self.position()),
+        })?;
+        Ok(Statement::Expression(expression))
+    }
+
     /// get an expression on the current pointer
     fn expression(&mut self) -> Result<Expression, ParserError> {
-        self.equality()
+        self.nested(Self::assignment)
     }
 
-    /// Try to parse an equality statement on the current position
-    fn equality(&mut self) -> Result<Expression, ParserError> {
-        let mut expression = self.comparison()?;
+    /// Try to parse an assignment on the current position of the pointer,
+    /// assignments are right associative
+    fn assignment(&mut self) -> Result<Expression, ParserError> {
+        let expression = self.pipeline()?;
 
-        while self.matches(&[Token::BangEqual, Token::EqualEqual]) {
-            let operator = self.previous().unwrap().token.clone();
-            let right = Box::new(self.comparison()?);
-            expression = Expression::Binary(BinaryExpression {
-                left: Box::new(expression),
-                operator,
-                right,
-            })
+        if self.matches(&[Token::Equal]) {
+            let equals = self.previous_token()?.position;
+            let value = Box::new(self.nested(Self::assignment)?);
+
+            return match expression {
+                Expression::Variable(VariableExpression { name, position }) => {
+                    self.node(Expression::Assign(AssignExpression { name, value, position }))
+                },
+                _ => Err(ParserError::InvalidAssignmentTarget(equals)),
+            };
         }
 
         Ok(expression)
     }
 
-    /// Try to parse a comparison on the current position of the pointer
-    fn comparison(&mut self) -> Result<Expression, ParserError> {
-        let mut expression = self.term()?;
+    /// Try to parse a pipeline on the current position of the pointer. Each
+    /// stage is desugared into a call, so `x |> f |> g` becomes `g(f(x))`
+    fn pipeline(&mut self) -> Result<Expression, ParserError> {
+        let mut expression = self.coalesce()?;
 
-        while self.matches(&[Token::Greater, Token::GreaterEqual, Token::Less, Token::LessEqual]) {
-            let operator = self.previous().unwrap().token.clone();
-            let right = Box::new(self.term()?);
-            expression = Expression::Binary(BinaryExpression { 
-                left: Box::new(expression),
-                operator, 
-                right, 
-            });
+        while self.matches(&[Token::PipeGreater]) {
+            let callee = Box::new(self.coalesce()?);
+            expression = self.node(Expression::Call(CallExpression {
+                callee,
+                arguments: vec![expression],
+            }))?;
         }
 
         Ok(expression)
     }
 
-    /// Try to parse terms on the current position of the pointer
-    fn term(&mut self) -> Result<Expression, ParserError> {
-        let mut expression = self.factor()?;
-
-        while self.matches(&[Token::Minus, Token::Plus]) {
-            let operator = self.previous().unwrap().token.clone();
-            let right = Box::new(self.factor()?);
-            expression = Expression::Binary(BinaryExpression { 
-                left: Box::new(expression), 
-                operator, 
-                right 
-            });
+    /// Parse a left associative chain of operands joined by any of the
+    /// operators, combining each pair into an expression with build
+    fn binary(&mut self, operators: &[Token], operand: Operand, build: Build) -> Result<Expression, ParserError> {
+        let mut expression = operand(self)?;
+
+        while self.matches(operators) {
+            expression = self.binary_right(expression, operand, build)?;
         }
 
         Ok(expression)
     }
 
+    /// Parse the right operand of a binary expression after its operator.
+    /// Kept apart from binary, as every nested expression recurses through
+    /// the frame of binary once for each level of precedence
+    fn binary_right(&mut self, left: Expression, operand: Operand, build: Build) -> Result<Expression, ParserError> {
+        let operator = self.previous_token()?;
+        let right = Box::new(operand(self)?);
+        self.node(build(Box::new(left), operator, right))
+    }
+
+    /// Try to parse a null coalescing on the current position of the pointer
+    fn coalesce(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::QuestionQuestion], Self::logical_or, |left, operator, right| {
+            Expression::Coalesce(CoalesceExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse a logical or on the current position of the pointer
+    fn logical_or(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::Or], Self::logical_and, |left, operator, right| {
+            Expression::Logical(LogicalExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse a logical and on the current position of the pointer
+    fn logical_and(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::And], Self::equality, |left, operator, right| {
+            Expression::Logical(LogicalExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse an equality statement on the current position
+    fn equality(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::BangEqual, Token::EqualEqual], Self::comparison, |left, operator, right| {
+            Expression::Binary(BinaryExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse a comparison on the current position of the pointer
+    fn comparison(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::Greater, Token::GreaterEqual, Token::Less, Token::LessEqual], Self::term, |left, operator, right| {
+            Expression::Binary(BinaryExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse terms on the current position of the pointer
+    fn term(&mut self) -> Result<Expression, ParserError> {
+        self.binary(&[Token::Minus, Token::Plus], Self::factor, |left, operator, right| {
+            Expression::Binary(BinaryExpression { left, operator, right })
+        })
+    }
+
     /// Try to parse factor on the current position of the pointer
     fn factor(&mut self) -> Result<Expression, ParserError> {
-        let mut expression = self.unary()?;
-
-        while self.matches(&[Token::Slash, Token::Star]) {
-            let operator = self.previous().unwrap().token.clone();
-            let right = Box::new(self.unary()?);
-            expression = Expression::Binary(BinaryExpression { 
-                left: Box::new(expression), 
-                operator, 
-                right 
-            });
+        self.binary(&[Token::Slash, Token::Star, Token::Percent], Self::power, |left, operator, right| {
+            Expression::Binary(BinaryExpression { left, operator, right })
+        })
+    }
+
+    /// Try to parse power on the current position of the pointer, it's right
+    /// associative so the right operand recurses into power again
+    fn power(&mut self) -> Result<Expression, ParserError> {
+        let expression = self.unary()?;
+
+        if self.matches(&[Token::StarStar]) {
+            let operator = self.previous_token()?;
+            let right = Box::new(self.nested(Self::power)?);
+            return self.node(Expression::Binary(BinaryExpression {
+                left: Box::new(expression),
+                operator,
+                right
+            }));
         }
 
         Ok(expression)
@@ -188,72 +826,1025 @@ impl Parser {
     /// Try to parse unary on the current position of the pointer
     fn unary(&mut self) -> Result<Expression, ParserError> {
         if self.matches(&[Token::Bang, Token::Minus]) {
-            let operator = self.previous().unwrap().token.clone();
-            let right = Box::new(self.unary()?);
-            return Ok(Expression::Unary(UnaryExpression {
+            let operator = self.previous_token()?;
+            let right = Box::new(self.nested(Self::unary)?);
+            return self.node(Expression::Unary(UnaryExpression {
                 operator,
                 right
             }));
         }
 
-        self.primary()
+        self.call()
     }
 
-    /// Try to parse a primary expression on the current position of the pointer
-    fn primary(&mut self) -> Result<Expression, ParserError> {
-        if self.matches(&[Token::False]) {
-            return Ok(Expression::Literal(LiteralExpression{ literal: Token::False}));
-        }
-        if self.matches(&[Token::True]) {
-            return Ok(Expression::Literal(LiteralExpression{ literal: Token::True}));
-        }
-        if self.matches(&[Token::Null]) {
-            return Ok(Expression::Literal(LiteralExpression{ literal: Token::Null}));
+    /// Try to parse a function call, or a chain of calls, on the current position of the pointer
+    fn call(&mut self) -> Result<Expression, ParserError> {
+        let mut expression = self.primary()?;
+
+        while self.matches(&[Token::LeftParenthesis, Token::LeftBracket, Token::Dot, Token::PlusPlus, Token::MinusMinus]) {
+            expression = self.postfix(expression)?;
         }
 
-        match self.peek().unwrap().token.clone() {
-            Token::String(s) => {
-                self.advance();
-                return Ok(Expression::Literal(LiteralExpression { literal: Token::String(s) }))
+        Ok(expression)
+    }
+
+    /// Parse the rest of a call, index, property or increment after the
+    /// expression it applies to. Kept apart from call, which every nested
+    /// expression recurses through
+    fn postfix(&mut self, expression: Expression) -> Result<Expression, ParserError> {
+        let operator = self.previous_token()?;
+
+        match operator.token {
+            Token::LeftParenthesis => {
+                let arguments = self.arguments(&Token::RightParenthesis)?;
+                self.node(Expression::Call(CallExpression {
+                    callee: Box::new(expression),
+                    arguments,
+                }))
             },
-            Token::Number(n) => {
-                self.advance();
-                return Ok(Expression::Literal(LiteralExpression { literal: Token::Number(n) }))
+            Token::LeftBracket => {
+                let index = self.expression()?;
+                self.consume(&Token::RightBracket)?;
+                self.node(Expression::Index(IndexExpression {
+                    target: Box::new(expression),
+                    index: Box::new(index),
+                }))
+            },
+            Token::Dot => {
+                let name = self.identifier()?;
+                self.node(Expression::Get(GetExpression {
+                    object: Box::new(expression),
+                    name,
+                }))
+            },
+            _ => {
+                // Only variables can be incremented, like only they can be assigned to
+                let Expression::Variable(VariableExpression { name, .. }) = expression else {
+                    return Err(ParserError::InvalidAssignmentTarget(operator.position));
+                };
+                self.node(Expression::Increment(IncrementExpression { name, operator }))
             },
-            _ => {}
         }
+    }
 
-        if self.matches(&[Token::LeftParenthesis]) {
-            let expression = self.expression()?;
-            self.consume(&Token::RightParenthesis)?;
-            return Ok(Expression::Grouping(GroupingExpression {
-                group: Box::new(expression)
-            }))
+    /// Parse the parts of a template string, text and embedded expressions,
+    /// into an expression joining them
+    fn interpolation(&mut self, parts: Vec<TemplatePart>) -> Result<Expression, ParserError> {
+        let mut expressions = vec![];
+        for part in parts {
+            expressions.push(match part {
+                TemplatePart::Literal(text) => self.node(Expression::Literal(LiteralExpression { literal: Token::String(text) }))?,
+                TemplatePart::Code(tokens) => self.embedded(tokens)?,
+            });
         }
 
+        self.node(Expression::Interpolation(InterpolationExpression { parts: expressions }))
+    }
+
+    /// Parse the tokens of an expression embedded in a template string. The
+    /// nested parser continues at the current depth and node count
+    fn embedded(&mut self, tokens: Vec<TokenMetadata>) -> Result<Expression, ParserError> {
+        // The Eof-token sits at the closing brace, so running into it means
+        // the brace came too early, not that the code ended
+        let end = tokens.last().map(|t| t.position).unwrap_or_default();
+        let closed_early = |err| match err {
+            ParserError::UnexpectedEof(_) => ParserError::Unexpected { found: Token::RightBrace, position: end, suggestion: None },
+            ParserError::Unexpected { found: Token::Eof, position, .. } => ParserError::Unexpected { found: Token::RightBrace, position, suggestion: None },
+            ParserError::Consume { expected, found: Token::Eof, position, .. } => ParserError::Consume { expected, found: Token::RightBrace, position, suggestion: None },
+            err => err,
+        };
 
-        Err(ParserError::Unexpected)
+        let mut parser = Parser { nodes: self.nodes, max_nodes: self.max_nodes, depth: self.depth, max_depth: self.max_depth, keywords: self.keywords.clone(), ..Parser::new(tokens) };
+        let expression = parser.expression().map_err(closed_early)?;
+        if let Some(rest) = parser.peek().filter(|t| t.token != Token::Eof) {
+            return Err(parser.unexpected(rest));
+        }
+
+        self.nodes = parser.nodes;
+        self.warnings.extend(parser.warnings);
+        Ok(expression)
     }
-}
 
+    /// Parse a block used as an expression, the opening brace is already
+    /// consumed. An expression right before the closing brace, without a
+    /// semicolon, is the value of the block
+    fn block_expression(&mut self) -> Result<Expression, ParserError> {
+        let mut statements = vec![];
+        let mut tail = None;
 
-#[cfg(test)]
-pub mod tests {
-    use crate::{scanner::Scannable, ast::AstPrinter};
-    use super::*;
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            if self.peek().is_some_and(|t| Self::starts_statement(&t.token)) {
+                statements.extend(self.declaration()?);
+                continue;
+            }
 
+            let expression = self.expression()?;
+            if self.check(&Token::RightBrace) {
+                tail = Some(Box::new(expression));
+                break;
+            }
+            self.consume(&Token::Semicolon).map_err(|_| ParserError::ExpectedSemicolon(self.position()))?;
+            statements.push(Statement::Expression(expression));
+        }
 
-    #[test]
-    fn basic() {
-        let tokens =  "2*(4-1.123)".tokens().unwrap();
-        
-        let mut parser = Parser::new(tokens);
-        let exp = parser.expression().unwrap();
+        self.consume(&Token::RightBrace).map_err(|_| ParserError::UnterminatedBlock(self.position()))?;
+        self.node(Expression::Block(BlockExpression { statements, tail }))
+    }
 
+    /// Whether a token starts a declaration or statement other than an
+    /// expression statement
+    fn starts_statement(token: &Token) -> bool {
+        matches!(token, Token::Fn | Token::Let | Token::For | Token::If | Token::While | Token::Print
+            | Token::Assert | Token::Include | Token::Return | Token::Break | Token::Continue)
+    }
+
+    /// Parse the entries of a map literal, the opening brace is already consumed
+    fn map(&mut self) -> Result<Expression, ParserError> {
+        let mut entries = vec![];
+        if !self.check(&Token::RightBrace) {
+            loop {
+                // Keys are literals, a bare name isn't taken to mean a string
+                let key = match self.peek().map(|t| &t.token) {
+                    Some(Token::String(_) | Token::Number(_) | Token::Integer(_) | Token::True | Token::False) => self.primary()?,
+                    _ => return Err(ParserError::ExpectedKey(self.position())),
+                };
+                self.consume(&Token::Colon)?;
+                entries.push((key, self.expression()?));
 
-        let printer = AstPrinter;
-        let exp_str = printer.print(exp).unwrap();
+                // A trailing comma is allowed
+                if !self.matches(&[Token::Comma]) || self.check(&Token::RightBrace) {
+                    break;
+                }
+            }
+        }
+        self.consume(&Token::RightBrace)?;
 
-        assert_eq!(exp_str, "(* 2 (group (- 4 1.123)))");
+        self.node(Expression::Map(MapExpression { entries }))
     }
-}
\ No newline at end of file
+
+    /// Parse a comma separated, possibly empty, list of expressions up to
+    /// and including the closing token, optionally with a trailing comma
+    fn arguments(&mut self, closing: &Token) -> Result<Vec<Expression>, ParserError> {
+        let mut arguments = vec![];
+        if !self.check(closing) {
+            loop {
+                arguments.push(self.expression()?);
+
+                // A trailing comma is allowed
+                if !self.matches(&[Token::Comma]) || self.check(closing) {
+                    break;
+                }
+            }
+        }
+        self.consume(closing)?;
+
+        Ok(arguments)
+    }
+
+    /// Parse the parameters and body of a lambda, after its opening parenthesis
+    fn lambda(&mut self) -> Result<Expression, ParserError> {
+        let params = self.parameters()?;
+        self.consume(&Token::Arrow)?;
+
+        let body = Box::new(self.expression()?);
+        self.node(Expression::Lambda(LambdaExpression { params, body }))
+    }
+
+    /// Parse a parenthesized expression, after its opening parenthesis
+    fn grouping(&mut self) -> Result<Expression, ParserError> {
+        let expression = self.expression()?;
+        self.consume(&Token::RightParenthesis)?;
+        self.node(Expression::Grouping(GroupingExpression {
+            group: Box::new(expression)
+        }))
+    }
+
+    /// Try to parse a primary expression on the current position of the pointer
+    fn primary(&mut self) -> Result<Expression, ParserError> {
+        if self.is_at_end() {
+            return Err(ParserError::UnexpectedEof(self.position()));
+        }
+
+        if let Some(literal) = self.literal() {
+            return self.node(Expression::Literal(LiteralExpression { literal }));
+        }
+        if let Some(TokenMetadata { token: Token::TemplateString(parts), .. }) = self.peek() {
+            let parts = parts.clone();
+            self.advance();
+            return self.interpolation(parts);
+        }
+        let position = self.peek().map(|t| t.position).unwrap_or_default();
+        if let Ok(name) = self.identifier() {
+            return self.node(Expression::Variable(VariableExpression { name, position }));
+        }
+
+        if self.check(&Token::LeftParenthesis) && self.is_lambda() {
+            self.advance();
+            return self.lambda();
+        }
+
+        if self.matches(&[Token::LeftParenthesis]) {
+            return self.grouping();
+        }
+
+        if self.matches(&[Token::LeftBracket]) {
+            let elements = self.arguments(&Token::RightBracket)?;
+            return self.node(Expression::Array(ArrayExpression { elements }));
+        }
+
+        // In an expression a brace opens a map if it is empty or its first
+        // key is followed by a colon, otherwise a block yielding a value
+        if self.check(&Token::LeftBrace) {
+            let map = matches!(self.peek_at(1), Some(TokenMetadata { token: Token::RightBrace, .. }))
+                || matches!(self.peek_at(2), Some(TokenMetadata { token: Token::Colon, .. }));
+            self.advance();
+
+            return if map { self.map() } else { self.block_expression() };
+        }
+
+        match self.peek() {
+            Some(found) => Err(self.unexpected(found)),
+            None => Err(ParserError::UnexpectedEof(self.position())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{scanner::{Position, Scannable}, ast::AstPrinter};
+    use super::*;
+
+
+    #[test]
+    fn basic() {
+        let exp = crate::parse_expression("2*(4-1.123)").unwrap();
+
+        assert_eq!(AstPrinter::new().print(exp).unwrap(), "(* 2 (group (- 4 1.123)))");
+    }
+
+    #[test]
+    fn operator_positions() {
+        let tokens = "1 +\n  -x * 2".tokens().unwrap();
+        let expression = Parser::new(tokens).parse().unwrap();
+
+        let Expression::Binary(plus) = &expression else { panic!("Expected a binary expression") };
+        assert_eq!(plus.operator.position, Position::new(1, 3));
+
+        let Expression::Binary(star) = plus.right.as_ref() else { panic!("Expected a binary expression") };
+        assert_eq!(star.operator.position, Position::new(2, 6));
+
+        let Expression::Unary(minus) = star.left.as_ref() else { panic!("Expected a unary expression") };
+        assert_eq!(minus.operator.position, Position::new(2, 3));
+    }
+
+    #[test]
+    fn print_statement() {
+        let tokens = "print 1 + 2;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        let [Statement::Print(expressions)] = &program[..] else { panic!("Expected a print statement") };
+        let [expression] = &expressions[..] else { panic!("Expected a single value") };
+        assert_eq!(ast(expression), "(+ 1 2)");
+    }
+
+    #[test]
+    fn print_several() {
+        let program = Parser::new("print a, 1 + 2, \"c\"; print;".tokens().unwrap()).parse_program().unwrap();
+
+        let [Statement::Print(expressions), Statement::Print(empty)] = &program[..] else { panic!("Expected two print statements") };
+        let printed: Vec<String> = expressions.iter().map(ast).collect();
+        assert_eq!(printed, vec!["a", "(+ 1 2)", "c"]);
+        assert!(empty.is_empty());
+
+        assert!(Parser::new("print a,;".tokens().unwrap()).parse_program().is_err());
+    }
+
+    #[test]
+    fn include_statement() {
+        let program = Parser::new("include \"utils.lhscript\";".tokens().unwrap()).parse_program().unwrap();
+        assert_eq!(program, vec![Statement::Include { path: String::from("utils.lhscript"), position: Position::new(1, 9) }]);
+
+        let errors = Parser::new("include utils;".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors, vec![ParserError::ExpectedIncludePath(Position::new(1, 9))]);
+    }
+
+    #[test]
+    fn expression_statement() {
+        let tokens = "42; print 1;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![
+            Statement::Expression(Expression::Literal(LiteralExpression { literal: Token::Integer(42) })),
+            Statement::Print(vec![Expression::Literal(LiteralExpression { literal: Token::Integer(1) })]),
+        ]);
+    }
+
+    #[test]
+    fn missing_semicolon() {
+        let tokens = "print 1 + 2".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedSemicolon(_)]));
+    }
+
+    #[test]
+    fn let_declaration() {
+        let tokens = "let x = 1 + 2; let y;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        let [Statement::Let { name, initializer: Some(initializer) }, y] = &program[..] else { panic!("Expected two let statements") };
+        assert_eq!(name, "x");
+        assert_eq!(ast(initializer), "(+ 1 2)");
+        assert_eq!(*y, Statement::Let { name: String::from("y"), initializer: None });
+    }
+
+    #[test]
+    fn let_several() {
+        let program = Parser::new("let a = 1, b = a + 1, c;".tokens().unwrap()).parse_program().unwrap();
+        assert_eq!(AstPrinter::new().print_program(&program).unwrap(), "(let a 1)\n(let b (+ a 1))\n(let c)");
+
+        let program = Parser::new("for (let i = 0, j = 2; i < j; i = i + 1) {}".tokens().unwrap()).parse_program().unwrap();
+        let [Statement::Block(statements)] = &program[..] else { panic!("Expected a block") };
+        assert!(matches!(statements[..], [Statement::Let { .. }, Statement::Let { .. }, Statement::While { .. }]));
+
+        let mut parser = Parser::new("let a, 1;".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedIdentifier(_)]));
+    }
+
+    #[test]
+    fn let_without_identifier() {
+        let tokens = "let = 5;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedIdentifier(_)]));
+    }
+
+    #[test]
+    fn variable() {
+        let tokens = "foo + 1".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let exp = parser.expression().unwrap();
+
+        let Expression::Binary(binary) = &exp else { panic!("Expected a binary expression") };
+        assert_eq!(*binary.left, var("foo", 1, 1));
+        assert_eq!(ast(&exp), "(+ foo 1)");
+    }
+
+    #[test]
+    fn logical_precedence() {
+        let tokens = "a || b && c".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let exp = parser.expression().unwrap();
+
+        assert_eq!(ast(&exp), "(|| a (&& b c))");
+    }
+
+    #[test]
+    fn logical_below_equality() {
+        let tokens = "a == b && c".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let exp = parser.expression().unwrap();
+
+        assert!(matches!(exp, Expression::Logical(LogicalExpression { operator: TokenMetadata { token: Token::And, .. }, .. })));
+    }
+
+    #[test]
+    fn coalesce_precedence() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).expression().unwrap());
+
+        assert_eq!(parse("a ?? b || c"), "(?? a (|| b c))");
+        assert_eq!(parse("a || b ?? c"), "(?? (|| a b) c)");
+        assert_eq!(parse("a ?? b ?? c"), "(?? (?? a b) c)");
+        assert_eq!(parse("x = a ?? 1"), "(= x (?? a 1))");
+    }
+
+    #[test]
+    fn member_access() {
+        let parse = |code: &str| Parser::new(code.tokens().unwrap()).expression();
+
+        assert_eq!(ast(&parse("a.b.c").unwrap()), "(. (. a b) c)");
+        assert_eq!(ast(&parse("a.b(1)[2]").unwrap()), "(index (call (. a b) 1) 2)");
+        assert_eq!(ast(&parse("1.foo").unwrap()), "(. 1 foo)");
+        assert_eq!(parse("1.").unwrap_err(), ParserError::ExpectedIdentifier(Position::new(1, 3)));
+    }
+
+    #[test]
+    fn word_operators() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).expression().unwrap());
+
+        assert_eq!(parse("true and false"), parse("true && false"));
+        assert_eq!(parse("a or b and c"), parse("a || b && c"));
+        assert_eq!(parse("not x"), parse("!x"));
+    }
+
+    /// Print an expression with the AST printer, as operator positions make
+    /// hand built expressions hard to compare against parsed ones
+    fn ast(expression: &Expression) -> String {
+        expression.accept(&mut AstPrinter::new()).unwrap()
+    }
+
+    fn var(name: &str, line: usize, column: usize) -> Expression {
+        Expression::Variable(VariableExpression { name: String::from(name), position: Position::new(line, column) })
+    }
+
+    fn print_number(n: i64) -> Box<Statement> {
+        Box::new(Statement::Print(vec![Expression::Literal(LiteralExpression { literal: Token::Integer(n) })]))
+    }
+
+    #[test]
+    fn if_statement() {
+        let tokens = "if (a) print 1;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![Statement::If {
+            condition: var("a", 1, 5),
+            then_branch: print_number(1),
+            else_branch: None,
+        }]);
+    }
+
+    #[test]
+    fn if_else_statement() {
+        let tokens = "if (a) print 1; else print 2;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![Statement::If {
+            condition: var("a", 1, 5),
+            then_branch: print_number(1),
+            else_branch: Some(print_number(2)),
+        }]);
+    }
+
+    #[test]
+    fn else_if_chain() {
+        let tokens = "if (a) print 1; else if (b) print 2; else print 3;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![Statement::If {
+            condition: var("a", 1, 5),
+            then_branch: print_number(1),
+            else_branch: Some(Box::new(Statement::If {
+                condition: var("b", 1, 26),
+                then_branch: print_number(2),
+                else_branch: Some(print_number(3)),
+            })),
+        }]);
+    }
+
+    #[test]
+    fn dangling_else() {
+        let tokens = "if (a) if (b) print 1; else print 2;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![Statement::If {
+            condition: var("a", 1, 5),
+            then_branch: Box::new(Statement::If {
+                condition: var("b", 1, 12),
+                then_branch: print_number(1),
+                else_branch: Some(print_number(2)),
+            }),
+            else_branch: None,
+        }]);
+    }
+
+    #[test]
+    fn if_missing_parenthesis() {
+        let tokens = "if (a print 1;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn while_statement() {
+        let tokens = "while (x < 10) x = x + 1;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        let [Statement::While { condition, body, increment: None }] = &program[..] else { panic!("Expected a while statement") };
+        assert_eq!(ast(condition), "(< x 10)");
+
+        let Statement::Expression(expression) = body.as_ref() else { panic!("Expected an expression statement") };
+        assert_eq!(ast(expression), "(= x (+ x 1))");
+    }
+
+    #[test]
+    fn while_missing_parenthesis() {
+        let mut parser = Parser::new("while x < 10) x = 1;".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedLeftParenthesis("while", _)]));
+
+        let mut parser = Parser::new("while (x < 10 x = 1;".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedRightParenthesis("while", _)]));
+    }
+
+    #[test]
+    fn invalid_assignment_target() {
+        let mut parser = Parser::new("1 + 2 = 3;".tokens().unwrap());
+
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::InvalidAssignmentTarget(_)]));
+    }
+
+    #[test]
+    fn for_statement() {
+        let tokens = "for (let i = 0; i < 3; i = i + 1) print i;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        // { let i = 0; while (i < 3) print i; } incrementing after each iteration
+        let [Statement::Block(outer)] = &program[..] else { panic!("Expected an outer block") };
+        let [initializer, Statement::While { condition, body, increment: Some(increment) }] = &outer[..] else { panic!("Expected initializer and while") };
+        assert_eq!(*initializer, Statement::Let {
+            name: String::from("i"),
+            initializer: Some(Expression::Literal(LiteralExpression { literal: Token::Integer(0) })),
+        });
+        assert_eq!(ast(condition), "(< i 3)");
+
+        assert_eq!(**body, Statement::Print(vec![var("i", 1, 41)]));
+        assert_eq!(ast(increment), "(= i (+ i 1))");
+    }
+
+    #[test]
+    fn for_without_clauses() {
+        let tokens = "for (;;) print 1;".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program, vec![Statement::While {
+            condition: Expression::Literal(LiteralExpression { literal: Token::True }),
+            body: print_number(1),
+            increment: None,
+        }]);
+    }
+
+    #[test]
+    fn empty_block() {
+        let mut parser = Parser::new("{}".tokens().unwrap());
+
+        assert_eq!(parser.parse_program().unwrap(), vec![Statement::Block(vec![])]);
+    }
+
+    #[test]
+    fn block() {
+        let mut parser = Parser::new("while (a) { let x; print 1; }".tokens().unwrap());
+
+        assert_eq!(parser.parse_program().unwrap(), vec![Statement::While {
+            condition: var("a", 1, 8),
+            body: Box::new(Statement::Block(vec![
+                Statement::Let { name: String::from("x"), initializer: None },
+                *print_number(1),
+            ])),
+            increment: None,
+        }]);
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let mut parser = Parser::new("while (a) { break; continue; }".tokens().unwrap());
+
+        let [Statement::While { body, .. }] = &parser.parse_program().unwrap()[..] else { panic!("Expected a while statement") };
+        assert_eq!(**body, Statement::Block(vec![Statement::Break, Statement::Continue]));
+
+        let mut parser = Parser::new("break".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedSemicolon(_)]));
+    }
+
+    #[test]
+    fn unterminated_block() {
+        let mut parser = Parser::new("{ print 1;".tokens().unwrap());
+
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::UnterminatedBlock(_)]));
+    }
+
+    #[test]
+    fn function_without_parameters() {
+        let mut parser = Parser::new("fn f() { print 1; }".tokens().unwrap());
+
+        assert_eq!(parser.parse_program().unwrap(), vec![Statement::Function {
+            name: String::from("f"),
+            params: vec![],
+            body: vec![*print_number(1)],
+        }]);
+    }
+
+    #[test]
+    fn function_with_parameters() {
+        let mut parser = Parser::new("fn add(a, b) { print a + b; }".tokens().unwrap());
+        let program = parser.parse_program().unwrap();
+
+        let [Statement::Function { name, params, body }] = &program[..] else { panic!("Expected a function") };
+        assert_eq!(name, "add");
+        assert_eq!(*params, vec![String::from("a"), String::from("b")]);
+
+        let [Statement::Print(expressions)] = &body[..] else { panic!("Expected a print statement") };
+        assert_eq!(expressions.iter().map(ast).collect::<Vec<_>>(), vec!["(+ a b)"]);
+    }
+
+    #[test]
+    fn arrays() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).parse().unwrap());
+
+        assert_eq!(parse("[]"), "(array)");
+        assert_eq!(parse("[1, 2 + 3]"), "(array 1 (+ 2 3))");
+        assert_eq!(parse("a[0][i + 1]"), "(index (index a 0) (+ i 1))");
+        assert_eq!(parse("f()[0](1)"), "(call (index (call f) 0) 1)");
+
+        let mut parser = Parser::new("a[0;".tokens().unwrap());
+        assert!(matches!(parser.parse(), Err(ParserError::Consume { expected: Token::RightBracket, .. })));
+    }
+
+    #[test]
+    fn increments() {
+        let parse = |code: &str| Parser::new(code.tokens().unwrap()).parse();
+
+        assert_eq!(ast(&parse("x++ + y--").unwrap()), "(+ (++ x) (-- y))");
+        assert!(matches!(parse("-x++").unwrap(), Expression::Unary(UnaryExpression { right, .. }) if matches!(*right, Expression::Increment(_))));
+
+        assert!(matches!(parse("1++"), Err(ParserError::InvalidAssignmentTarget(_))));
+        assert!(matches!(parse("a[0]--"), Err(ParserError::InvalidAssignmentTarget(_))));
+        assert!(matches!(parse("x++++"), Err(ParserError::InvalidAssignmentTarget(_))));
+    }
+
+    #[test]
+    fn lambdas() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).parse().unwrap());
+
+        let Expression::Lambda(lambda) = Parser::new("(a, b) -> a + b".tokens().unwrap()).parse().unwrap() else {
+            panic!("Expected a lambda")
+        };
+        assert_eq!(lambda.params, vec![String::from("a"), String::from("b")]);
+        assert_eq!(ast(&lambda.body), "(+ a b)");
+
+        assert_eq!(parse("() -> 1"), "(lambda () 1)");
+        assert_eq!(parse("(x) -> (y) -> x * y"), "(lambda (x) (lambda (y) (* x y)))");
+        assert_eq!(parse("((a) -> a)(1)"), "(call (group (lambda (a) a)) 1)");
+        assert_eq!(parse("(a) - 1"), "(- (group a) 1)");
+    }
+
+    #[test]
+    fn trailing_commas() {
+        let parse = |code: &str| Parser::new(code.tokens().unwrap()).parse_program();
+
+        assert_eq!(parse("f(1, 2,);").unwrap(), parse("f(1, 2);").unwrap());
+        assert_eq!(parse("[1,];").unwrap(), parse("[1];").unwrap());
+        assert_eq!(parse("m = {\"a\": 1,};").unwrap(), parse("m = {\"a\": 1};").unwrap());
+        assert_eq!(parse("fn f(a, b,) {}").unwrap(), parse("fn f(a, b) {}").unwrap());
+
+        assert!(parse("f(,);").is_err());
+        assert!(parse("[1,,];").is_err());
+        assert!(parse("fn f(,) {}").is_err());
+    }
+
+    #[test]
+    fn template_strings() {
+        let parse = |code: &str| Parser::new(code.tokens().unwrap()).parse();
+
+        let Expression::Interpolation(template) = parse(r#""a ${x + 1} b""#).unwrap() else { panic!("Expected an interpolation") };
+        let parts: Vec<String> = template.parts.iter().map(ast).collect();
+        assert_eq!(parts, vec!["a ", "(+ x 1)", " b"]);
+
+        assert!(matches!(parse(r#""${}""#), Err(ParserError::Unexpected { found: Token::RightBrace, position, .. }) if position == Position::new(1, 4)));
+        assert!(matches!(parse(r#""${1 2}""#), Err(ParserError::Unexpected { found: Token::Integer(2), position, .. }) if position == Position::new(1, 6)));
+        assert!(matches!(parse(r#""${(1}""#), Err(ParserError::Consume { found: Token::RightBrace, .. })));
+        assert!(matches!(parse(r#""${1 +}""#), Err(err) if !err.is_incomplete()));
+    }
+
+    #[test]
+    fn block_expressions() {
+        let parse = |code: &str| Parser::new(code.tokens().unwrap()).parse_program();
+
+        let program = parse("let y = { let x = 1; x + 1 };").unwrap();
+        let [Statement::Let { initializer: Some(Expression::Block(block)), .. }] = &program[..] else { panic!("Expected a block initializer") };
+        assert_eq!(block.statements.len(), 1);
+        assert_eq!(block.tail.as_deref().map(ast).as_deref(), Some("(+ x 1)"));
+
+        let program = parse("let y = { f(); };").unwrap();
+        let [Statement::Let { initializer: Some(Expression::Block(block)), .. }] = &program[..] else { panic!("Expected a block initializer") };
+        assert_eq!(block.statements.len(), 1);
+        assert!(block.tail.is_none());
+
+        assert_eq!(ast(&Parser::new("{ if (a) print a; 2 } * 3".tokens().unwrap()).parse().unwrap()), "(* (block (if a (print a)) 2) 3)");
+        assert!(matches!(parse("let y = { 1 2 };").unwrap_err()[..], [ParserError::ExpectedSemicolon(_), ..]));
+        assert!(parse("let y = { 1;").is_err());
+    }
+
+    #[test]
+    fn maps() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).parse().unwrap());
+
+        assert_eq!(parse("{}"), "(map)");
+        assert_eq!(parse("{\"a\": 1, \"b\": x + 1}[\"b\"]"), "(index (map (a 1) (b (+ x 1))) b)");
+
+        assert_eq!(parse("{1: \"a\", true: 2.5}"), "(map (1 a) (true 2.5))");
+
+        let mut parser = Parser::new("{ a: 1 }".tokens().unwrap());
+        assert!(matches!(parser.parse(), Err(ParserError::ExpectedKey(_))));
+        let mut parser = Parser::new("{ null: 1 }".tokens().unwrap());
+        assert!(matches!(parser.parse(), Err(ParserError::ExpectedKey(_))));
+
+        // A brace starting a statement still opens a block
+        let mut parser = Parser::new("{ print 1; } let m = { \"a\": 1 };".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap()[..], [Statement::Block(_), Statement::Let { .. }]));
+    }
+
+    #[test]
+    fn power() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).parse().unwrap());
+
+        assert_eq!(parse("2 ** 3 ** 2"), "(** 2 (** 3 2))");
+        assert_eq!(parse("2 * 3 ** 2"), "(* 2 (** 3 2))");
+        assert_eq!(parse("-2 ** 2"), "(** (- 2) 2)");
+    }
+
+    #[test]
+    fn reset() {
+        let mut parser = Parser::new("1 + 2".tokens().unwrap());
+        assert_eq!(ast(&parser.parse().unwrap()), "(+ 1 2)");
+
+        parser.reset("-x * 3".tokens().unwrap());
+        assert_eq!(ast(&parser.parse().unwrap()), "(* (- x) 3)");
+    }
+
+    #[test]
+    fn return_statement() {
+        let mut parser = Parser::new("fn f() { return; return 1; }".tokens().unwrap());
+
+        assert_eq!(parser.parse_program().unwrap(), vec![Statement::Function {
+            name: String::from("f"),
+            params: vec![],
+            body: vec![
+                Statement::Return(None),
+                Statement::Return(Some(Expression::Literal(LiteralExpression { literal: Token::Integer(1) }))),
+            ],
+        }]);
+    }
+
+    #[test]
+    fn function_parameter_errors() {
+        let mut parser = Parser::new("fn f(a, 1) {}".tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::ExpectedIdentifier(_)]));
+
+        let params = (0..=MAX_PARAMETERS).map(|i| format!("a{}", i)).collect::<Vec<_>>().join(", ");
+        let code = format!("fn f({}) {{}}", params);
+        let mut parser = Parser::new(code.as_str().tokens().unwrap());
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::TooManyParameters(_)]));
+    }
+
+    #[test]
+    fn assignment_in_condition() {
+        let mut parser = Parser::new("if (x = 1) print x;".tokens().unwrap());
+        parser.parse_program().unwrap();
+        assert!(matches!(parser.warnings[..], [Warning::AssignmentInCondition(_)]));
+        assert_eq!(parser.warnings[0].to_string(), "Assignment used as condition at 1:5, did you mean '=='?");
+
+        let mut parser = Parser::new("if (x == 1) print x;".tokens().unwrap());
+        parser.parse_program().unwrap();
+        assert!(parser.warnings.is_empty());
+
+        let mut parser = Parser::new("while ((x = 1)) print x;".tokens().unwrap());
+        parser.parse_program().unwrap();
+        assert!(parser.warnings.is_empty());
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let mut parser = Parser::new("fn f(x) {\n  return x;\n  print \"never\";\n  print x;\n}".tokens().unwrap());
+        parser.parse_program().unwrap();
+        assert_eq!(parser.warnings, vec![Warning::UnreachableCode(Position::new(3, 3))]);
+        assert_eq!(parser.warnings[0].to_string(), "Unreachable code at 3:3, it follows a return");
+
+        let mut parser = Parser::new("fn f(x) { if (x) return 1; return 2; }".tokens().unwrap());
+        parser.parse_program().unwrap();
+        assert!(parser.warnings.is_empty());
+    }
+
+    #[test]
+    fn call_without_arguments() {
+        let mut parser = Parser::new("f()".tokens().unwrap());
+
+        assert_eq!(parser.expression().unwrap(), Expression::Call(CallExpression {
+            callee: Box::new(var("f", 1, 1)),
+            arguments: vec![],
+        }));
+    }
+
+    #[test]
+    fn call_with_arguments() {
+        let mut parser = Parser::new("f(a, 1 + 2)".tokens().unwrap());
+
+        assert_eq!(AstPrinter::new().print(parser.expression().unwrap()).unwrap(), "(call f a (+ 1 2))");
+    }
+
+    #[test]
+    fn chained_calls() {
+        let mut parser = Parser::new("f()(1)".tokens().unwrap());
+
+        assert_eq!(parser.expression().unwrap(), Expression::Call(CallExpression {
+            callee: Box::new(Expression::Call(CallExpression {
+                callee: Box::new(var("f", 1, 1)),
+                arguments: vec![],
+            })),
+            arguments: vec![Expression::Literal(LiteralExpression { literal: Token::Integer(1) })],
+        }));
+    }
+
+    #[test]
+    fn call_missing_parenthesis() {
+        let mut parser = Parser::new("f(1, 2;".tokens().unwrap());
+
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::Consume { expected: Token::RightParenthesis, found: Token::Semicolon, .. }]));
+    }
+
+    #[test]
+    fn node_budget() {
+        let code = format!("print 1{};", " + 1".repeat(100));
+
+        let mut parser = Parser::new(code.as_str().tokens().unwrap()).with_max_nodes(Some(201));
+        assert!(parser.parse_program().is_ok());
+
+        let mut parser = Parser::new(code.as_str().tokens().unwrap()).with_max_nodes(Some(100));
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::TooManyNodes(100, _)]));
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth: usize| format!("print {}1{};", "(".repeat(depth), ")".repeat(depth));
+
+        let code = nested(MAX_DEPTH - 1);
+        assert!(Parser::new(code.as_str().tokens().unwrap()).parse_program().is_ok());
+        let code = nested(100_000);
+        let errors = Parser::new(code.as_str().tokens().unwrap()).parse_program().unwrap_err();
+        assert!(matches!(errors[..], [ParserError::TooDeep(MAX_DEPTH, _)]));
+
+        let code = format!("{}{}", "{".repeat(100), "}".repeat(100));
+        let mut parser = Parser::new(code.as_str().tokens().unwrap()).with_max_depth(50);
+        assert!(matches!(parser.parse_program().unwrap_err()[..], [ParserError::TooDeep(50, _)]));
+
+        let code = format!("{}x", "- ".repeat(100_000));
+        assert!(matches!(Parser::new(code.as_str().tokens().unwrap()).parse(), Err(ParserError::TooDeep(MAX_DEPTH, _))));
+
+        // Right associative operators nest as deeply
+        let code = format!("{}1", "x = ".repeat(5000));
+        assert!(matches!(Parser::new(code.as_str().tokens().unwrap()).parse(), Err(ParserError::TooDeep(MAX_DEPTH, _))));
+        let code = format!("{}2", "2 ** ".repeat(5000));
+        assert!(matches!(Parser::new(code.as_str().tokens().unwrap()).parse(), Err(ParserError::TooDeep(MAX_DEPTH, _))));
+
+        // So do the bodies of statements and functions
+        for code in [
+            format!("{}print 1;", "if (true) ".repeat(50_000)),
+            format!("{}print 1; else print 2;", "if (true) print 0; else ".repeat(50_000)),
+            format!("{}print 1;", "while (false) ".repeat(50_000)),
+            format!("{}print 1;", "for (;;) ".repeat(50_000)),
+            format!("{}{}", "fn f() {".repeat(20_000), "}".repeat(20_000)),
+        ] {
+            let errors = Parser::new(code.as_str().tokens().unwrap()).parse_program().unwrap_err();
+            assert!(matches!(errors[..], [ParserError::TooDeep(MAX_DEPTH, _)]), "{}...", &code[..20]);
+        }
+        let code = format!("{}print 1;", "if (true) ".repeat(MAX_DEPTH - 1));
+        assert!(Parser::new(code.as_str().tokens().unwrap()).parse_program().is_ok());
+    }
+
+    #[test]
+    fn suggest_keyword() {
+        let errors = Parser::new("fn f() wile (true) {}".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Expected '{' but found identifier 'wile' at 1:8, did you mean 'while'?");
+
+        let errors = Parser::new("fn f() count".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Expected '{' but found identifier 'count' at 1:8");
+
+        // Misspelled keywords starting a statement
+        let errors = Parser::new("wile (x) {}".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Unexpected identifier 'wile' at 1:1, did you mean 'while'?");
+        let errors = Parser::new("fucn f() {}".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Unexpected identifier 'fucn' at 1:1, did you mean 'fn'?");
+        let errors = Parser::new("let x = 1;\nretrun x;".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Unexpected identifier 'retrun' at 2:1, did you mean 'return'?");
+        let errors = Parser::new("count x;".tokens().unwrap()).parse_program().unwrap_err();
+        assert_eq!(errors[..], [ParserError::ExpectedSemicolon(Position::new(1, 7))]);
+
+        // Suggestions come from the keywords the code was scanned with
+        let keywords: crate::scanner::Keywords = [(String::from("mientras"), Token::While)].into();
+        let tokens = crate::scanner::TokenStream::with_keywords("mientas (x) {}", keywords.clone()).collect::<Result<Vec<_>, _>>().unwrap();
+        let errors = Parser::new(tokens).with_keywords(keywords.clone()).parse_program().unwrap_err();
+        assert_eq!(errors[0].to_string(), "Unexpected identifier 'mientas' at 1:1, did you mean 'mientras'?");
+        let tokens = crate::scanner::TokenStream::with_keywords("wile (x) {}", keywords.clone()).collect::<Result<Vec<_>, _>>().unwrap();
+        let errors = Parser::new(tokens).with_keywords(keywords).parse_program().unwrap_err();
+        assert_eq!(errors[..], [ParserError::ExpectedSemicolon(Position::new(1, 10))]);
+    }
+
+    #[test]
+    fn multiple_errors() {
+        let mut parser = Parser::new("print ); let = 1; print 2;".tokens().unwrap());
+
+        let errors = parser.parse_program().unwrap_err();
+        assert!(matches!(errors[..], [ParserError::Unexpected { found: Token::RightParenthesis, .. }, ParserError::ExpectedIdentifier(_)]));
+    }
+
+    #[test]
+    fn recover_at_keyword() {
+        let mut parser = Parser::new("let x = 1 print x; print (;".tokens().unwrap());
+
+        let errors = parser.parse_program().unwrap_err();
+        assert!(matches!(errors[..], [ParserError::ExpectedSemicolon(_), ParserError::Unexpected { .. }]));
+    }
+
+    #[test]
+    fn empty_token_list() {
+        let mut parser = Parser::new(vec![]);
+        assert!(matches!(parser.parse(), Err(ParserError::UnexpectedEof(_))));
+
+        let mut parser = Parser::new(vec![]);
+        assert_eq!(parser.parse_program().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn missing_eof() {
+        let mut tokens = "print 1;".tokens().unwrap();
+        tokens.pop();
+        let mut parser = Parser::new(tokens);
+        assert_eq!(parser.parse_program().unwrap(), vec![*print_number(1)]);
+
+        let mut tokens = "1 +".tokens().unwrap();
+        tokens.pop();
+        let mut parser = Parser::new(tokens);
+        assert!(matches!(parser.parse(), Err(ParserError::UnexpectedEof(_))));
+
+        let mut tokens = "print (1".tokens().unwrap();
+        tokens.pop();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn pipeline() {
+        let parse = |code: &str| ast(&Parser::new(code.tokens().unwrap()).expression().unwrap());
+
+        assert_eq!(parse("x |> sum"), parse("sum(x)"));
+        assert_eq!(parse("x |> f |> g"), parse("g(f(x))"));
+        assert_eq!(parse("a + 1 |> f"), parse("f(a + 1)"));
+    }
+
+    #[test]
+    fn peek_at() {
+        let mut parser = Parser::new("a b".tokens().unwrap());
+        parser.advance();
+
+        assert_eq!(parser.peek_at(0), parser.peek());
+        assert_eq!(parser.peek_at(1).map(|t| &t.token), Some(&Token::Eof));
+        assert_eq!(parser.peek_at(2), None);
+        assert_eq!(parser.current, 1);
+    }
+
+    #[test]
+    fn consume_number() {
+        let mut parser = Parser::new("42 x".tokens().unwrap());
+
+        assert_eq!(parser.consume_number(), Some(Token::Integer(42)));
+        assert_eq!(parser.current, 1);
+        assert_eq!(parser.consume_number(), None);
+        assert_eq!(parser.current, 1);
+    }
+
+    #[test]
+    fn consume_string() {
+        let mut parser = Parser::new("\"foo\" 42".tokens().unwrap());
+
+        assert_eq!(parser.consume_string(), Some(String::from("foo")));
+        assert_eq!(parser.current, 1);
+        assert_eq!(parser.consume_string(), None);
+        assert_eq!(parser.current, 1);
+    }
+
+    #[test]
+    fn modulo() {
+        let tokens = "7 % 2".tokens().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let exp = parser.expression().unwrap();
+
+        assert_eq!(ast(&exp), "(% 7 2)");
+    }
+}
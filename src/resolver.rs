@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use crate::{ast::*, errors::{ParserError, ScriptError}, scanner::Position};
+
+/// Static pass over a parsed program, finding reads of variables that are
+/// never declared before anything runs
+pub struct Resolver {
+    /// Names declared in each scope, innermost last. A name maps to false
+    /// while its own initializer is resolved
+    scopes: Vec<HashMap<String, bool>>,
+
+    /// Index of the scope of each function body being resolved, innermost last
+    functions: Vec<usize>,
+
+    /// Names read in function bodies without a declaration in sight, with
+    /// the number of scopes that may still declare them. A function may be
+    /// called after a later declaration in a scope around it, so each name is
+    /// checked as those scopes end
+    deferred: Vec<(String, Position, usize)>,
+
+    /// Whether a file is included, it may declare anything so undeclared
+    /// names can no longer be told apart from ones it declares
+    included: bool,
+
+    errors: Vec<ParserError>,
+}
+
+impl Resolver {
+    /// Create a resolver aware of already defined globals, like the built-in
+    /// functions or variables from earlier lines in the REPL
+    pub fn new(globals: impl IntoIterator<Item = String>) -> Self {
+        Resolver {
+            scopes: vec![globals.into_iter().map(|name| (name, true)).collect()],
+            functions: vec![],
+            deferred: vec![],
+            included: false,
+            errors: vec![],
+        }
+    }
+
+    /// Resolve a whole program, failing with every problem found
+    pub fn resolve(mut self, program: &[Statement]) -> Result<(), ScriptError> {
+        for statement in program {
+            statement.accept(&mut self)?;
+        }
+
+        for (name, position, _) in std::mem::take(&mut self.deferred) {
+            if !self.included && !self.scopes[0].contains_key(&name) {
+                self.errors.push(ParserError::UndeclaredVariable { name, position });
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ScriptError::ParserErrors(self.errors))
+        }
+    }
+
+    /// Declare a name in the innermost scope, not yet readable unless it was
+    /// already defined there, then the initializer sees the old value
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.entry(String::from(name)).or_insert(false);
+        }
+    }
+
+    /// Make a declared name readable
+    fn define(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(String::from(name), true);
+        }
+    }
+
+    /// Check a read of a name against the scopes around it
+    fn read(&mut self, name: &str, position: Position) {
+        for scope in self.scopes.iter().rev() {
+            if let Some(&defined) = scope.get(name) {
+                if !defined {
+                    self.errors.push(ParserError::ReadInOwnInitializer { name: String::from(name), position });
+                }
+                return;
+            }
+        }
+
+        if let Some(&function) = self.functions.last() {
+            self.deferred.push((String::from(name), position, function));
+        } else if !self.included {
+            self.errors.push(ParserError::UndeclaredVariable { name: String::from(name), position });
+        }
+    }
+
+    /// Resolve in a new innermost scope. Names held back from functions
+    /// inside it are settled if the scope declared them after all
+    fn scoped(&mut self, resolve: impl FnOnce(&mut Self) -> Result<(), ScriptError>) -> Result<(), ScriptError> {
+        self.scopes.push(HashMap::new());
+        let result = resolve(self);
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        // Only scopes around the function can declare a name it reads, and
+        // once this one is gone only the scopes around it are left
+        let index = self.scopes.len();
+        self.deferred.retain_mut(|(name, _, scopes)| {
+            if *scopes <= index {
+                return true;
+            }
+            *scopes = index;
+            !scope.contains_key(name)
+        });
+
+        result
+    }
+
+    /// Resolve the body of a function in a scope of its own, holding the parameters
+    fn function(&mut self, params: &[String], body: impl FnOnce(&mut Self) -> Result<(), ScriptError>) -> Result<(), ScriptError> {
+        self.functions.push(self.scopes.len());
+        let result = self.scoped(|resolver| {
+            for param in params {
+                resolver.define(param);
+            }
+            body(resolver)
+        });
+        self.functions.pop();
+        result
+    }
+}
+
+impl ExpressionVisitor<()> for Resolver {
+    fn visit_unary(&mut self, expr: &UnaryExpression) -> Result<(), ScriptError> {
+        expr.right.accept(self)
+    }
+
+    fn visit_binary(&mut self, expr: &BinaryExpression) -> Result<(), ScriptError> {
+        expr.left.accept(self)?;
+        expr.right.accept(self)
+    }
+
+    fn visit_logical(&mut self, expr: &LogicalExpression) -> Result<(), ScriptError> {
+        expr.left.accept(self)?;
+        expr.right.accept(self)
+    }
+
+    fn visit_coalesce(&mut self, expr: &CoalesceExpression) -> Result<(), ScriptError> {
+        expr.left.accept(self)?;
+        expr.right.accept(self)
+    }
+
+    fn visit_grouping(&mut self, expr: &GroupingExpression) -> Result<(), ScriptError> {
+        expr.group.accept(self)
+    }
+
+    fn visit_literal(&mut self, _expr: &LiteralExpression) -> Result<(), ScriptError> {
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, expr: &VariableExpression) -> Result<(), ScriptError> {
+        self.read(&expr.name, expr.position);
+        Ok(())
+    }
+
+    fn visit_assign(&mut self, expr: &AssignExpression) -> Result<(), ScriptError> {
+        expr.value.accept(self)?;
+        self.read(&expr.name, expr.position);
+        Ok(())
+    }
+
+    fn visit_increment(&mut self, expr: &IncrementExpression) -> Result<(), ScriptError> {
+        self.read(&expr.name, expr.operator.position);
+        Ok(())
+    }
+
+    fn visit_call(&mut self, expr: &CallExpression) -> Result<(), ScriptError> {
+        expr.callee.accept(self)?;
+        expr.arguments.iter().try_for_each(|argument| argument.accept(self))
+    }
+
+    fn visit_array(&mut self, expr: &ArrayExpression) -> Result<(), ScriptError> {
+        expr.elements.iter().try_for_each(|element| element.accept(self))
+    }
+
+    fn visit_interpolation(&mut self, expr: &InterpolationExpression) -> Result<(), ScriptError> {
+        expr.parts.iter().try_for_each(|part| part.accept(self))
+    }
+
+    fn visit_index(&mut self, expr: &IndexExpression) -> Result<(), ScriptError> {
+        expr.target.accept(self)?;
+        expr.index.accept(self)
+    }
+
+    fn visit_get(&mut self, expr: &GetExpression) -> Result<(), ScriptError> {
+        // Only the object is a variable, the name is looked up on it
+        expr.object.accept(self)
+    }
+
+    fn visit_map(&mut self, expr: &MapExpression) -> Result<(), ScriptError> {
+        for (key, value) in &expr.entries {
+            key.accept(self)?;
+            value.accept(self)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_lambda(&mut self, expr: &LambdaExpression) -> Result<(), ScriptError> {
+        self.function(&expr.params, |resolver| expr.body.accept(resolver))
+    }
+
+    fn visit_block_expression(&mut self, expr: &BlockExpression) -> Result<(), ScriptError> {
+        self.scoped(|resolver| {
+            expr.statements.iter().try_for_each(|statement| statement.accept(resolver))?;
+            expr.tail.as_ref().map_or(Ok(()), |tail| tail.accept(resolver))
+        })
+    }
+}
+
+impl StatementVisitor<()> for Resolver {
+    fn visit_expression_statement(&mut self, expression: &Expression) -> Result<(), ScriptError> {
+        expression.accept(self)
+    }
+
+    fn visit_print(&mut self, expressions: &[Expression]) -> Result<(), ScriptError> {
+        expressions.iter().try_for_each(|expression| expression.accept(self))
+    }
+
+    fn visit_assert(&mut self, condition: &Expression, message: Option<&Expression>, _position: Position) -> Result<(), ScriptError> {
+        condition.accept(self)?;
+        message.map_or(Ok(()), |message| message.accept(self))
+    }
+
+    fn visit_include(&mut self, _path: &str, _position: Position) -> Result<(), ScriptError> {
+        self.included = true;
+        Ok(())
+    }
+
+    fn visit_let(&mut self, name: &str, initializer: Option<&Expression>) -> Result<(), ScriptError> {
+        self.declare(name);
+        if let Some(initializer) = initializer {
+            initializer.accept(self)?;
+        }
+        self.define(name);
+
+        Ok(())
+    }
+
+    fn visit_if(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) -> Result<(), ScriptError> {
+        condition.accept(self)?;
+        then_branch.accept(self)?;
+        else_branch.map_or(Ok(()), |else_branch| else_branch.accept(self))
+    }
+
+    fn visit_while(&mut self, condition: &Expression, body: &Statement, increment: Option<&Expression>) -> Result<(), ScriptError> {
+        condition.accept(self)?;
+        body.accept(self)?;
+        increment.map_or(Ok(()), |increment| increment.accept(self))
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) -> Result<(), ScriptError> {
+        self.scoped(|resolver| statements.iter().try_for_each(|statement| statement.accept(resolver)))
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<(), ScriptError> {
+        // Defined right away, so the function can call itself
+        self.define(name);
+        self.function(params, |resolver| body.iter().try_for_each(|statement| statement.accept(resolver)))
+    }
+
+    fn visit_return(&mut self, value: Option<&Expression>) -> Result<(), ScriptError> {
+        value.map_or(Ok(()), |value| value.accept(self))
+    }
+
+    fn visit_break(&mut self) -> Result<(), ScriptError> {
+        Ok(())
+    }
+
+    fn visit_continue(&mut self) -> Result<(), ScriptError> {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scanner::Scannable, parser::Parser};
+
+    fn resolve(code: &str) -> Result<(), Vec<ParserError>> {
+        let program = Parser::new(code.tokens().unwrap()).parse_program().unwrap();
+
+        match Resolver::new([String::from("clock")]).resolve(&program) {
+            Ok(()) => Ok(()),
+            Err(ScriptError::ParserErrors(errors)) => Err(errors),
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn well_formed() {
+        assert!(resolve("let x = 1; { let y = x; print y + clock(); }").is_ok());
+        assert!(resolve("fn f(n) { return g(n); } fn g(n) { return n; } print f(1);").is_ok());
+        assert!(resolve("fn fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }").is_ok());
+        assert!(resolve("let x = 1; let x = x + 1;").is_ok());
+        assert!(resolve("for (let i = 0; i < 3; i++) print ((a) -> a * i)(2);").is_ok());
+        assert!(resolve("include \"utils.lhscript\"; print helper(1);").is_ok());
+
+        // A function may read a name declared later in a scope around it
+        assert!(resolve("{ fn f() { return z; } let z = 1; print f(); }").is_ok());
+        assert!(resolve("fn outer() { fn inner() { return z; } let z = 1; return inner(); } print outer();").is_ok());
+        assert!(resolve("let x = 1; x = 2; { fn f() { y = 1; } let y = 0; f(); }").is_ok());
+    }
+
+    #[test]
+    fn undeclared() {
+        let errors = resolve("let count = 1;\nprint cuont;").unwrap_err();
+        assert_eq!(errors, vec![ParserError::UndeclaredVariable { name: String::from("cuont"), position: Position::new(2, 7) }]);
+
+        assert!(matches!(resolve("{ let y = 1; } print y;").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+        assert!(matches!(resolve("fn f() { return z; }").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+        assert!(matches!(resolve("print x; let x = 1;").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+        assert!(matches!(resolve("print x; include \"utils.lhscript\";").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+
+        // Only scopes around the function count, not ones beside it
+        assert!(matches!(resolve("{ fn f() { return z; } } { let z = 1; }").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+        assert!(matches!(resolve("fn f() { fn g() { return z; } } { let z = 1; }").unwrap_err()[..], [ParserError::UndeclaredVariable { .. }]));
+
+        let errors = resolve("let x = 1;\nundeclared = x;").unwrap_err();
+        assert_eq!(errors, vec![ParserError::UndeclaredVariable { name: String::from("undeclared"), position: Position::new(2, 1) }]);
+    }
+
+    #[test]
+    fn own_initializer() {
+        let errors = resolve("let x = x;").unwrap_err();
+        assert_eq!(errors, vec![ParserError::ReadInOwnInitializer { name: String::from("x"), position: Position::new(1, 9) }]);
+
+        assert!(matches!(resolve("let y = 1; { let y = [y]; }").unwrap_err()[..], [ParserError::ReadInOwnInitializer { .. }]));
+    }
+}
@@ -1,31 +1,177 @@
 use crate::token::*;
-use std::{fmt::Display};
+use std::{collections::HashMap, fmt::Display, ops::Range};
 use crate::errors::ScannerError;
+use crate::symbol::Symbol;
 
+/// Limit of templates nested in the embedded expressions of templates, each
+/// level scans the inner template recursively
+const MAX_TEMPLATE_DEPTH: usize = 64;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Position {
     line: usize,
     column: usize,
+
+    /// Name of the file, or other source, the code was read from
+    source: Option<Symbol>,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column, source: None }
+    }
+
+    /// Line number, starting at 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the character on the line, starting at 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Name of the source of the code, if it has one
+    pub fn source(&self) -> Option<&'static str> {
+        self.source.map(|source| source.as_str())
+    }
 }
 
+/// Positions in named sources are prefixed by the name, e.g. script.lhscript:4:12
+///
+/// The alternate form, `{:#}`, zero pads the line and column, e.g. 0004:012,
+/// so positions of several tokens line up in columns
 impl Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f,"{}:{}", self.line, self.column)
+        if let Some(source) = self.source {
+            write!(f, "{}:", source)?;
+        }
+        if f.alternate() {
+            write!(f, "{:04}:{:03}", self.line, self.column)
+        } else {
+            write!(f,"{}:{}", self.line, self.column)
+        }
+    }
+}
+
+/// Byte range of a lexeme in the code of the script
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    offset: usize,
+    length: usize,
+}
+
+impl Span {
+    /// Byte offset of the first character of the lexeme
+    pub fn start(&self) -> usize {
+        self.offset
+    }
+
+    /// Byte offset just past the last character of the lexeme
+    pub fn end(&self) -> usize {
+        self.offset + self.length
+    }
+
+    /// The lexeme's byte range, usable for slicing the code
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+}
+
+/// Mapping from the text of a keyword to its token
+pub type Keywords = HashMap<String, Token>;
+
+/// The reserved keywords of the language
+pub fn default_keywords() -> Keywords {
+    [
+        ("assert", Token::Assert),
+        ("break", Token::Break),
+        ("class", Token::Class),
+        ("continue", Token::Continue),
+        ("else", Token::Else),
+        ("false", Token::False),
+        ("fn", Token::Fn),
+        ("for", Token::For),
+        ("if", Token::If),
+        ("include", Token::Include),
+        ("null", Token::Null),
+        ("print", Token::Print),
+        ("return", Token::Return),
+        ("super", Token::Super),
+        ("this", Token::This),
+        ("true", Token::True),
+        ("let", Token::Let),
+        ("while", Token::While),
+
+        // Word aliases for the logical operators
+        ("and", Token::And),
+        ("or", Token::Or),
+        ("not", Token::Bang),
+    ].into_iter()
+        .map(|(keyword, token)| (String::from(keyword), token))
+        .collect()
+}
+
+/// The keyword closest to a word, if the word is likely a misspelling of it.
+/// Short words need to be closer, so `x` isn't taken for `or`
+pub fn suggest_keyword(word: &str, keywords: &Keywords) -> Option<String> {
+    let length = word.chars().count();
+
+    keywords.keys()
+        .map(|keyword| (edit_distance(word, keyword), keyword))
+        .filter(|(distance, _)| *distance <= 2 && distance * 2 <= length)
+        .min()
+        .map(|(_, keyword)| keyword.clone())
+}
+
+/// Levenshtein distance, the number of inserted, removed or replaced
+/// characters turning one word into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
     }
+
+    previous[b.len()]
 }
 
 #[derive(Debug)]
 /// Scanner is an iterator object over a vector of characters making up the code of the script
 struct Scanner {
-    /// A vector of characters containing the code of the script
-    code: Vec<char>,
-
-    /// Current position in the vector
-    current: usize,
+    /// The code of the script
+    code: String,
 
     /// Current position in the code file (line, column)
     position: Position,
+
+    /// Current byte offset in the code file, always on a character boundary
+    offset: usize,
+
+    /// Spans of all comments skipped so far
+    comments: Vec<Span>,
+
+    /// Words scanned as keywords rather than identifiers
+    keywords: Keywords,
+
+    /// Number of columns between tab stops, a tab advances to the next one
+    tab_width: usize,
+
+    /// Whether comments and whitespace are scanned as tokens rather than skipped
+    trivia: bool,
+
+    /// Whether the last token scanned can end an operand, so a dot after it
+    /// is member access or a range rather than the start of a number
+    after_operand: bool,
+
+    /// Number of template strings the code being scanned is embedded in
+    templates: usize,
 }
 
 /// Make the scanner object into an iterator over a 2-character window with next being an Option<char>
@@ -34,42 +180,66 @@ impl Iterator for Scanner {
 
     /// Get next window of the current character and an Option<char> of the next character
     fn next(&mut self) -> Option<Self::Item> {
-        // TODO: Is there a nicer way to dereference Option<&char> to Option<char> ?
-        if let Some(&curr) = self.code.get(self.current) {
-            self.advance();
+        let curr = self.peek()?;
+        self.advance();
 
-            if let Some(&next) = self.code.get(self.current) {
-                Some((curr, Some(next)))
-            } else {
-                Some((curr, None))
-            }
-        } else {
-            None
-        }
+        Some((curr, self.peek()))
     }
 }
 
 impl Scanner {
     /// Create new scanner based on a &str of code
     fn new(code: &str) -> Self {
+        Self::with_keywords(code, default_keywords())
+    }
+
+    /// Create new scanner with its own set of keywords, e.g. for a localized dialect
+    fn with_keywords(code: &str, keywords: Keywords) -> Self {
         Scanner {
-            code: code.chars().collect(),
-            current: 0,
-            position: Position { line: 0, column: 0 }
+            code: String::from(code),
+            position: Position::new(1, 0),
+            offset: 0,
+            comments: vec![],
+            keywords,
+            tab_width: 1,
+            trivia: false,
+            after_operand: false,
+            templates: 0,
         }
     }
 
-    /// Reset scanner to the start of the code
-    fn reset(&mut self) {
-        self.current = 0;
-        self.position.line = 1;
-        self.position.column = 0;
+    /// Start over on new code, reusing the allocation of the code buffer
+    fn reset_with(&mut self, code: &str) {
+        self.code.clear();
+        self.code.push_str(code);
+        // The code is new, but still read from the same source
+        self.position = Position { line: 1, column: 0, source: self.position.source };
+        self.offset = 0;
+        self.comments.clear();
+        self.after_operand = false;
     }
 
     /// Advance one step without getting the iterator output from self.next()
     fn advance(&mut self) {
-        self.current += 1;
-        self.position.column += 1;
+        let curr = self.peek();
+        if let Some(curr) = curr {
+            self.offset += curr.len_utf8();
+        }
+        // Columns count characters, not bytes, up to the next tab stop for tabs
+        self.position.column = match curr {
+            Some('\t') => (self.position.column / self.tab_width + 1) * self.tab_width,
+            _ => self.position.column + 1,
+        };
+    }
+
+    /// Peek at the character on the current position without advancing
+    fn peek(&self) -> Option<char> {
+        self.code[self.offset..].chars().next()
+    }
+
+    /// Peek at the character after the current position without advancing
+    fn peek_next(&self) -> Option<char> {
+        self.code[self.offset..].chars().nth(1)
     }
 
     /// Newline and return column to zero
@@ -78,28 +248,79 @@ impl Scanner {
         self.position.column = 0;
     }
 
+    /// Record the span of a comment starting at offset and ending at the current
+    /// position, in trivia mode the comment is also a token
+    fn comment(&mut self, offset: usize, token: fn(String) -> Token) -> Option<Token> {
+        self.comments.push(Span { offset, length: self.offset - offset });
+        self.trivia.then(|| token(self.code[offset..self.offset].to_string()))
+    }
+
+    /// Skip whitespace, in trivia mode consuming the rest of the run of
+    /// whitespace, line breaks included, into a single token
+    fn whitespace(&mut self) -> Option<Token> {
+        if !self.trivia {
+            return None;
+        }
+
+        while let Some(curr) = self.peek().filter(|c| c.is_whitespace()) {
+            self.advance();
+            if curr == '\n' {
+                self.newline();
+            }
+        }
+        Some(Token::Whitespace)
+    }
+
     /// Scan a single line comment from current position
     fn scan_line_comment(&mut self) -> Result<(),ScannerError> {
-        // Consume iterator until newline
-        for (_, next) in self.by_ref() {
-            if next == Some('\n') {
+        // Consume iterator until newline, a Windows line ending included
+        while let Some((_, next)) = self.next() {
+            if next == Some('\n') || (next == Some('\r') && self.peek_next() == Some('\n')) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scan a '#' comment from current position. Unlike after "//", there's
+    /// no second character of the delimiter to skip, so the comment may end
+    /// right away
+    fn scan_hash_comment(&mut self) -> Result<(), ScannerError> {
+        // Consume iterator up to newline, a Windows line ending included
+        while let Some(curr) = self.peek() {
+            if curr == '\n' || (curr == '\r' && self.peek_next() == Some('\n')) {
                 break;
             }
+            self.advance();
         }
         Ok(())
     }
 
+    /// Scan a possibly nested multiline comment from current position
     fn scan_multiline_comment(&mut self) -> Result<(), ScannerError> {
-        // Consume iterator until newline
+        let position = self.position;
+
+        // Consume the '*' of the opening delimiter
+        self.advance();
+
+        // Consume iterator until all opened comments are closed
+        let mut depth = 1;
         while let Some((curr, next)) = self.next() {
             match (curr, next) {
                 ('\n', _) => self.newline(),
-                ('*', Some('/')) => {self.advance(); break},
-                (_, None) => return Err(ScannerError::UnterminatedMultilineComment(self.position)),
+                ('/', Some('*')) => {self.advance(); depth += 1},
+                ('*', Some('/')) => {
+                    self.advance();
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                },
                 _ => {}
             }
         }
-        Ok(())
+
+        Err(ScannerError::UnterminatedMultilineComment(position))
     }
 
     /// Scan a number literal from current position
@@ -107,12 +328,48 @@ impl Scanner {
         let position = self.position;
 
         let mut number = String::from(curr);
-        if next.is_some_and(|n| n.is_numeric() || n == '.') {
-            for (curr, next) in self.by_ref() {
-                number.push(curr);
-                if !next.is_some_and(|n|n.is_numeric() || n == '.') {
-                    break;
+        if next.is_some_and(|n| n.is_numeric() || n == '.' || n == '_') {
+            loop {
+                match (self.peek(), self.peek_next()) {
+                    (Some(n), _) if n.is_numeric() || n == '_' => number.push(n),
+                    // A dot is only a decimal point if a digit follows, 1.foo is member access
+                    (Some('.'), Some(n)) if n.is_numeric() || n == '_' => number.push('.'),
+                    _ => break,
                 }
+                self.advance();
+            }
+        }
+
+        // Exponent, e.g. 1.5e10 or 2E-3
+        if self.peek().is_some_and(|n| n == 'e' || n == 'E') {
+            number.push(self.peek().unwrap());
+            self.advance();
+
+            if let Some(sign) = self.peek().filter(|&n| n == '+' || n == '-') {
+                number.push(sign);
+                self.advance();
+            }
+
+            if !self.peek().is_some_and(|n| n.is_numeric()) {
+                return Err(ScannerError::NumberLiteralParsingError(position));
+            }
+
+            while let Some(digit) = self.peek().filter(|&n| n.is_numeric() || n == '_') {
+                number.push(digit);
+                self.advance();
+            }
+        }
+
+        // Digit separators are only allowed between two digits, e.g. 1_000_000
+        if !valid_separators(&number) {
+            return Err(ScannerError::NumberLiteralParsingError(position));
+        }
+        number.retain(|c| c != '_');
+
+        // Whole numbers are integers, unless they are too large for one
+        if !number.contains(['.', 'e', 'E']) {
+            if let Ok(integer) = number.parse() {
+                return Ok(Token::Integer(integer));
             }
         }
 
@@ -126,77 +383,274 @@ impl Scanner {
 
     /// Scan a string literal from current position
     fn scan_string_literal(&mut self) -> Result<Token, ScannerError> {
-        // TODO: Add support for escape characters like '\n', '\\' or '\"' 
-        // TODO: Error on newline in string
+        let position = self.position;
+
+        if self.peek() == Some('"') && self.peek_next() == Some('"') {
+            self.advance();
+            self.advance();
+            return self.scan_multiline_string_literal(position);
+        }
 
         let mut string = String::new();
-        while let Some((curr, next)) = self.next() {
-            string.push(curr);
-            if next == Some('"') {
-                self.advance();
-                break;
+        let mut parts = vec![];
+        let mut escape = Ok(());
+        loop {
+            match self.next() {
+                Some(('"', _)) => break,
+                Some(('$', Some('{'))) => self.scan_interpolation(&mut string, &mut parts)?,
+                Some(('\n', _)) => return Err(ScannerError::NewlineInString(self.position)),
+                // An escaped newline continues the string on the next line
+                Some(('\\', Some('\n'))) => {
+                    self.advance();
+                    self.newline();
+                },
+                Some(('\\', Some('\r'))) if self.peek_next() == Some('\n') => {
+                    self.advance();
+                    self.advance();
+                    self.newline();
+                },
+                Some(('\\', _)) => self.push_escape_sequence(&mut string, &mut escape),
+                Some((curr, _)) => string.push(curr),
+                None => return Err(ScannerError::UnterminatedString(position)),
             }
         }
 
-        Ok(Token::String(string))
+        escape?;
+        Ok(Self::template(string, parts))
     }
 
-    /// Scan a keyword or identifier from current position
-    fn scan_keyword_or_identifier(&mut self, initial: char) -> Result<Token, ScannerError>{
-        let mut identifier = String::from(initial);
-        for (curr, next) in self.by_ref() {
-            identifier.push(curr);
-            if !next.is_some_and(|n|n.is_alphanumeric() || n == '_') {
-                break;
+    /// Scan a triple quoted string literal, which may span multiple lines, from current position
+    fn scan_multiline_string_literal(&mut self, position: Position) -> Result<Token, ScannerError> {
+        let mut string = String::new();
+        let mut parts = vec![];
+        let mut escape = Ok(());
+        loop {
+            match self.next() {
+                Some(('$', Some('{'))) => self.scan_interpolation(&mut string, &mut parts)?,
+                Some(('"', Some('"'))) if self.peek_next() == Some('"') => {
+                    self.advance();
+                    self.advance();
+                    break;
+                },
+                Some(('\n', _)) => {
+                    self.newline();
+                    string.push('\n');
+                },
+                // Windows line endings are kept as plain newlines
+                Some(('\r', Some('\n'))) => {},
+                // An escaped newline joins the lines, leaving out the newline
+                Some(('\\', Some('\n'))) => {
+                    self.advance();
+                    self.newline();
+                },
+                Some(('\\', Some('\r'))) if self.peek_next() == Some('\n') => {
+                    self.advance();
+                    self.advance();
+                    self.newline();
+                },
+                Some(('\\', _)) => self.push_escape_sequence(&mut string, &mut escape),
+                Some((curr, _)) => string.push(curr),
+                None => return Err(ScannerError::UnterminatedString(position)),
             }
         }
 
-        // This is the list of reserved keywords
-        Ok(match identifier.as_str() {
-            "class" => Token::Class,
-            "else" => Token::Else,
-            "false" => Token::False,
-            "fn" => Token::Fn,
-            "for" => Token::For,
-            "if" => Token::If,
-            "null" => Token::Null,
-            "print" => Token::Print,
-            "return" => Token::Return,
-            "super" => Token::Super,
-            "this" => Token::This,
-            "true" => Token::True,
-            "let" => Token::Let,
-            "while" => Token::While,
-            ident => Token::Identifier(String::from(ident)),
-        })
+        escape?;
+        Ok(Self::template(string, parts))
     }
 
-    /// Parse all tokens from the underlaying vector of characters
-    fn tokens(&mut self) -> Result<Vec<TokenMetadata>, ScannerError> {
-        self.reset();
+    /// Scan an escape sequence in a string literal onto the string. An
+    /// invalid one is kept as the error of the string, which is still
+    /// scanned up to its closing quote, so the rest of it isn't taken as code
+    fn push_escape_sequence(&mut self, string: &mut String, escape: &mut Result<(), ScannerError>) {
+        match self.scan_escape_sequence() {
+            Ok(char) => string.push(char),
+            Err(err) => if escape.is_ok() {
+                *escape = Err(err);
+            },
+        }
+    }
 
-        let mut tokens: Vec<TokenMetadata> = vec![];
+    /// Scan the tokens of an expression embedded in a string, from the '$'
+    /// of the opening "${" up to and including the closing brace. The text
+    /// scanned before it becomes a part of its own
+    fn scan_interpolation(&mut self, string: &mut String, parts: &mut Vec<TemplatePart>) -> Result<(), ScannerError> {
+        let position = self.position;
+        self.advance();
+
+        if !string.is_empty() {
+            parts.push(TemplatePart::Literal(std::mem::take(string)));
+        }
+
+        // Each embedded template recurses once more, like nested expressions
+        // do in the parser
+        if self.templates >= MAX_TEMPLATE_DEPTH {
+            return Err(ScannerError::InterpolationTooDeep(position));
+        }
+        self.templates += 1;
+
+        // Comments and whitespace in the expression are always skipped, the
+        // whole template is a single token anyway
+        let trivia = std::mem::replace(&mut self.trivia, false);
+        let mut tokens = vec![];
+        let mut depth = 0;
+        let result = loop {
+            match self.scan_token() {
+                Ok(token) if token.token == Token::RightBrace && depth == 0 => {
+                    tokens.push(TokenMetadata { token: Token::Eof, span: Span { offset: token.span.offset, length: 0 }, ..token });
+                    break Ok(());
+                },
+                // Running into the end of the code, or the closing quote
+                // taken as the start of another string, leaves it unclosed
+                Ok(TokenMetadata { token: Token::Eof, .. }) | Err(ScannerError::UnterminatedString(_)) => {
+                    break Err(ScannerError::UnterminatedInterpolation(position));
+                },
+                Ok(token) => {
+                    match token.token {
+                        Token::LeftBrace => depth += 1,
+                        Token::RightBrace => depth -= 1,
+                        _ => {},
+                    }
+                    tokens.push(token);
+                },
+                Err(err) => break Err(err),
+            }
+        };
+        self.trivia = trivia;
+        self.templates -= 1;
+
+        parts.push(TemplatePart::Code(tokens));
+        result
+    }
+
+    /// The token of a scanned string literal, a plain string unless it
+    /// embeds expressions
+    fn template(string: String, mut parts: Vec<TemplatePart>) -> Token {
+        if parts.is_empty() {
+            return Token::String(string);
+        }
+        if !string.is_empty() {
+            parts.push(TemplatePart::Literal(string));
+        }
+
+        Token::TemplateString(parts)
+    }
+
+    /// Scan a character literal from current position
+    fn scan_char_literal(&mut self) -> Result<Token, ScannerError> {
+        let position = self.position;
+
+        let char = match self.next() {
+            Some(('\\', _)) => self.scan_escape_sequence()?,
+            Some((curr, _)) if curr != '\'' && curr != '\n' => curr,
+            _ => return Err(ScannerError::InvalidCharLiteral(position)),
+        };
+
+        match self.next() {
+            Some(('\'', _)) => Ok(Token::Char(char)),
+            _ => Err(ScannerError::InvalidCharLiteral(position)),
+        }
+    }
+
+    /// Scan the character following a backslash in a string or character literal
+    fn scan_escape_sequence(&mut self) -> Result<char, ScannerError> {
+        let position = self.position;
+
+        match self.next() {
+            Some(('n', _)) => Ok('\n'),
+            Some(('t', _)) => Ok('\t'),
+            Some(('r', _)) => Ok('\r'),
+            Some(('0', _)) => Ok('\0'),
+            Some(('\\', _)) => Ok('\\'),
+            Some(('\'', _)) => Ok('\''),
+            Some(('"', _)) => Ok('"'),
+            Some(('$', _)) => Ok('$'),
+            Some(('u', _)) => self.scan_unicode_escape(position),
+            _ => Err(ScannerError::InvalidEscapeSequence(position)),
+        }
+    }
+
+    /// Scan the braced hexadecimal code point of a unicode escape, e.g. \u{1F600},
+    /// following the u
+    fn scan_unicode_escape(&mut self, position: Position) -> Result<char, ScannerError> {
+        if self.peek() != Some('{') {
+            return Err(ScannerError::InvalidUnicodeEscape(position));
+        }
+        self.advance();
+
+        let mut digits = String::new();
+        while let Some(digit) = self.peek().filter(|n| n.is_ascii_hexdigit()) {
+            digits.push(digit);
+            self.advance();
+        }
+
+        if digits.is_empty() || digits.len() > 6 || self.peek() != Some('}') {
+            return Err(ScannerError::InvalidUnicodeEscape(position));
+        }
+        self.advance();
+
+        // Surrogates and values above 10FFFF aren't characters
+        u32::from_str_radix(&digits, 16).ok()
+            .and_then(char::from_u32)
+            .ok_or(ScannerError::InvalidUnicodeEscape(position))
+    }
+
+    /// Scan a keyword or identifier from current position
+    fn scan_keyword_or_identifier(&mut self, initial: char) -> Result<Token, ScannerError>{
+        let start = self.offset - initial.len_utf8();
+        while self.peek().is_some_and(|n| n.is_alphanumeric() || n == '_') {
+            self.advance();
+        }
 
+        let word = &self.code[start..self.offset];
+        Ok(match self.keywords.get(word) {
+            Some(keyword) => keyword.clone(),
+            None => Token::Identifier(Symbol::intern(word)),
+        })
+    }
+
+    /// Scan the next token from the underlaying vector of characters, skipping
+    /// whitespace and comments. Returns an Eof-token once the code is exhausted
+    fn scan_token(&mut self) -> Result<TokenMetadata, ScannerError> {
         while let Some((curr, next)) = self.next() {
             let position = self.position;
+            let offset = self.offset - curr.len_utf8();
 
             let token = match (curr, next) {
-                // Newline
-                ('\n', _ )                  => {self.newline(); None},
+                // Newline, the \r of a Windows line ending is skipped before it
+                ('\n', _ )                  => {self.newline(); self.whitespace()},
+                ('\r', Some('\n'))          => self.whitespace(),
 
                 // Whitespace
-                _ if curr.is_whitespace()   => None,
+                _ if curr.is_whitespace()   => self.whitespace(),
 
                 // Comments
-                ('/', Some('/')) => {self.scan_line_comment()?; None},
-                ('/', Some('*')) => {self.scan_multiline_comment()?; None},
+                ('/', Some('/')) => {self.scan_line_comment()?; self.comment(offset, Token::LineComment)},
+                ('#', _) => {self.scan_hash_comment()?; self.comment(offset, Token::LineComment)},
+                ('/', Some('*')) => {self.scan_multiline_comment()?; self.comment(offset, Token::BlockComment)},
+
+                // Compound assignment, must be matched before their single character prefixes
+                ('+', Some('=')) => { self.advance(); Some(Token::PlusEqual)},
+                ('+', Some('+')) => { self.advance(); Some(Token::PlusPlus)},
+                ('-', Some('-')) => { self.advance(); Some(Token::MinusMinus)},
+                ('-', Some('=')) => { self.advance(); Some(Token::MinusEqual)},
+                ('-', Some('>')) => { self.advance(); Some(Token::Arrow)},
+                ('*', Some('=')) => { self.advance(); Some(Token::StarEqual)},
+                ('/', Some('=')) => { self.advance(); Some(Token::SlashEqual)},
+
+                // Power, must be matched before the single character star
+                ('*', Some('*')) => { self.advance(); Some(Token::StarStar)},
 
                 // Single character tokens
                 ('(', _) => Some(Token::LeftParenthesis),
                 (')', _) => Some(Token::RightParenthesis),
                 ('{', _) => Some(Token::LeftBrace),
                 ('}', _) => Some(Token::RightBrace),
+                ('[', _) => Some(Token::LeftBracket),
+                (']', _) => Some(Token::RightBracket),
                 (',', _) => Some(Token::Comma),
+                // A leading dot belongs to a number, e.g. .5, unless it follows an
+                // operand like in x.5 or 1..2
+                ('.', Some(n)) if n.is_numeric() && !self.after_operand => Some(self.scan_number_literal(curr, next)?),
                 ('.', _) => Some(Token::Dot),
                 ('-', _) => Some(Token::Minus),
                 ('+', _) => Some(Token::Plus),
@@ -204,6 +658,8 @@ impl Scanner {
                 (';', _) => Some(Token::Semicolon),
                 ('/', _) => Some(Token::Slash),
                 ('*', _) => Some(Token::Star),
+                ('%', _) => Some(Token::Percent),
+                ('?', Some('?')) => { self.advance(); Some(Token::QuestionQuestion)},
                 ('?', _) => Some(Token::Question),
 
                 // One or two character tokens
@@ -211,53 +667,202 @@ impl Scanner {
                 ('=', Some('=')) => { self.advance(); Some(Token::EqualEqual)},
                 ('>', Some('=')) => { self.advance(); Some(Token::GreaterEqual)},
                 ('<', Some('=')) => { self.advance(); Some(Token::LessEqual)},
+                ('<', Some('<')) => { self.advance(); Some(Token::LeftShift)},
+                // Three characters, the longest match wins over >> and >
+                ('>', Some('>')) if self.peek_next() == Some('>') => { self.advance(); self.advance(); Some(Token::UnsignedRightShift)},
+                ('>', Some('>')) => { self.advance(); Some(Token::RightShift)},
                 ('&', Some('&')) => { self.advance(); Some(Token::And)},
                 ('|', Some('|')) => { self.advance(); Some(Token::Or)},
+                ('|', Some('>')) => { self.advance(); Some(Token::PipeGreater)},
                 ('!', _) => Some(Token::Bang),
                 ('=', _) => Some(Token::Equal),
                 ('>', _) => Some(Token::Greater),
                 ('<', _) => Some(Token::Less),
+                ('&', _) => Some(Token::Ampersand),
+                ('|', _) => Some(Token::Pipe),
+                ('^', _) => Some(Token::Caret),
 
                 // Keywords and identifiers
                 _ if curr.is_alphabetic() => Some(self.scan_keyword_or_identifier(curr)?),
 
-                // Number literals
+                // Number literals, a leading digit separator is scanned as a malformed number
                 _ if curr.is_numeric() => Some(self.scan_number_literal(curr, next)?),
+                ('_', Some(n)) if n.is_numeric() => Some(self.scan_number_literal(curr, next)?),
 
                 // String literals
                 ('"', _) => Some(self.scan_string_literal()?),
 
+                // Character literals
+                ('\'', _) => Some(self.scan_char_literal()?),
+
                 // Unexpected -> Error
                 _ => return Err(ScannerError::UnexpectedToken(position)),
             };
 
             if let Some(token) = token {
-                tokens.push(TokenMetadata { token, position });
+                if !matches!(token, Token::LineComment(_) | Token::BlockComment(_) | Token::Whitespace) {
+                    self.after_operand = matches!(token, Token::Number(_) | Token::Integer(_) | Token::Identifier(_) | Token::Dot
+                        | Token::RightParenthesis | Token::RightBracket | Token::RightBrace);
+                }
+
+                let span = Span { offset, length: self.offset - offset };
+                return Ok(TokenMetadata { token, position, end: self.position, span });
             }
         }
 
-        // Add Eof-token
+        // Eof-token
         self.advance();
-        tokens.push(TokenMetadata {
+        Ok(TokenMetadata {
             token: Token::Eof,
             position: self.position,
-        });
+            end: self.position,
+            span: Span { offset: self.offset, length: 0 },
+        })
+    }
+}
 
-        Ok(tokens)
+/// Lazy stream of tokens scanned from a string of code, ending with an Eof-token.
+/// The stream stops after the Eof-token or the first error
+pub struct TokenStream {
+    scanner: Scanner,
+    finished: bool,
+}
+
+impl TokenStream {
+    /// Create a new token stream over a &str of code
+    pub fn new(code: &str) -> Self {
+        TokenStream {
+            scanner: Scanner::new(code),
+            finished: false,
+        }
+    }
+
+    /// Create a new token stream scanning its own set of keywords, see [default_keywords]
+    pub fn with_keywords(code: &str, keywords: Keywords) -> Self {
+        TokenStream {
+            scanner: Scanner::with_keywords(code, keywords),
+            finished: false,
+        }
+    }
+
+    /// Name the source the code is read from, e.g. a file, for the positions
+    /// of the tokens and errors to refer to
+    pub fn named(mut self, source: &str) -> Self {
+        self.scanner.position.source = Some(Symbol::intern(source));
+        self
+    }
+
+    /// Count tabs as reaching the next multiple of tab_width columns, like an
+    /// editor shows them, rather than as a single column
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.scanner.tab_width = tab_width.max(1);
+        self
+    }
+
+    /// Scan comments and whitespace as tokens too, so the code can be put
+    /// back together exactly from the tokens, e.g. by a formatter
+    pub fn with_trivia(mut self) -> Self {
+        self.scanner.trivia = true;
+        self
+    }
+
+    /// Spans of the comments skipped so far
+    pub fn comments(&self) -> &[Span] {
+        &self.scanner.comments
+    }
+
+    /// Restart the stream on new code, reusing the scanner's buffers. Useful
+    /// when tokenizing many small snippets, like in a REPL
+    pub fn reset_with(&mut self, code: &str) {
+        self.scanner.reset_with(code);
+        self.finished = false;
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = Result<TokenMetadata, ScannerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.scanner.scan_token();
+        self.finished = match &result {
+            Ok(token) => token.token == Token::Eof,
+            Err(_) => true,
+        };
+
+        Some(result)
     }
 }
 
+/// Check that every digit separator in a number literal sits between two digits
+fn valid_separators(number: &str) -> bool {
+    let chars: Vec<char> = number.chars().collect();
+    chars.iter()
+        .enumerate()
+        .filter(|(_, &c)| c == '_')
+        .all(|(i, _)| i > 0 && chars[i - 1].is_numeric() && chars.get(i + 1).is_some_and(|n| n.is_numeric()))
+}
+
 /// Scannable trait can be put on enything that can be converted to a string of code
 pub trait Scannable {
     fn tokens(&self) -> Result<Vec<TokenMetadata>, ScannerError>;
+
+    /// Like tokens, but scans all the code, reporting every error rather than
+    /// only the first one
+    fn tokens_collect(&self) -> Result<Vec<TokenMetadata>, Vec<ScannerError>>;
+
+    /// Like tokens_collect, but hands back the tokens that did scan along
+    /// with the errors
+    fn tokens_partial(&self) -> (Vec<TokenMetadata>, Vec<ScannerError>);
+
+    /// Like tokens, but with comments and whitespace as tokens too
+    fn tokens_with_trivia(&self) -> Result<Vec<TokenMetadata>, ScannerError>;
 }
 
 /// Implement scannable for &str
 impl Scannable for &str {
     /// Scan a string of code for tokens
     fn tokens(&self) -> Result<Vec<TokenMetadata>, ScannerError> {
+        TokenStream::new(self).collect()
+    }
+
+    /// Scan a string of code for tokens, skipping past errors
+    fn tokens_collect(&self) -> Result<Vec<TokenMetadata>, Vec<ScannerError>> {
+        match self.tokens_partial() {
+            (tokens, errors) if errors.is_empty() => Ok(tokens),
+            (_, errors) => Err(errors),
+        }
+    }
+
+    /// Scan a string of code for tokens, keeping both the tokens and the
+    /// errors skipped past
+    fn tokens_partial(&self) -> (Vec<TokenMetadata>, Vec<ScannerError>) {
         let mut scanner = Scanner::new(self);
-        scanner.tokens()
+        let mut tokens = vec![];
+        let mut errors = vec![];
+
+        loop {
+            match scanner.scan_token() {
+                Ok(token) if token.token == Token::Eof => {
+                    tokens.push(token);
+                    break;
+                },
+                Ok(token) => tokens.push(token),
+                // The scanner has already moved past the offending code, so
+                // scanning just carries on from there
+                Err(err) => errors.push(err),
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    /// Scan a string of code for tokens, keeping comments and whitespace
+    fn tokens_with_trivia(&self) -> Result<Vec<TokenMetadata>, ScannerError> {
+        TokenStream::new(self).with_trivia().collect()
     }
 }
 
@@ -272,39 +877,64 @@ mod tests {
             .unwrap()
     }
 
+    /// Token and position of a scanned token, leaving out its span
+    fn without_span(token: &TokenMetadata) -> (Token, Position) {
+        (token.token.clone(), token.position)
+    }
+
     #[test]
     fn single_character_tokens() {
         let tokens = test_tokens();
 
-        assert_eq!(tokens[0],  TokenMetadata {token: Token::LeftParenthesis,  position: Position {line: 2, column:  1}});
-        assert_eq!(tokens[1],  TokenMetadata {token: Token::RightParenthesis, position: Position {line: 2, column:  2}});
-        assert_eq!(tokens[2],  TokenMetadata {token: Token::LeftBrace,        position: Position {line: 2, column:  3}});
-        assert_eq!(tokens[3],  TokenMetadata {token: Token::RightBrace,       position: Position {line: 2, column:  4}});
-        assert_eq!(tokens[4],  TokenMetadata {token: Token::Comma,            position: Position {line: 2, column:  5}});
-        assert_eq!(tokens[5],  TokenMetadata {token: Token::Dot,              position: Position {line: 2, column:  6}});
-        assert_eq!(tokens[6],  TokenMetadata {token: Token::Minus,            position: Position {line: 2, column:  7}});
-        assert_eq!(tokens[7],  TokenMetadata {token: Token::Plus,             position: Position {line: 2, column:  8}});
-        assert_eq!(tokens[8],  TokenMetadata {token: Token::Colon,            position: Position {line: 2, column:  9}});
-        assert_eq!(tokens[9],  TokenMetadata {token: Token::Semicolon,        position: Position {line: 2, column: 10}});
-        assert_eq!(tokens[10], TokenMetadata {token: Token::Star,             position: Position {line: 2, column: 11}});
-        assert_eq!(tokens[11], TokenMetadata {token: Token::Slash,            position: Position {line: 2, column: 12}});
-        assert_eq!(tokens[12], TokenMetadata {token: Token::Question,         position: Position {line: 2, column: 13}});
+        assert_eq!(without_span(&tokens[0]),  (Token::LeftParenthesis,  Position::new(2,  1)));
+        assert_eq!(without_span(&tokens[1]),  (Token::RightParenthesis, Position::new(2,  2)));
+        assert_eq!(without_span(&tokens[2]),  (Token::LeftBrace,        Position::new(2,  3)));
+        assert_eq!(without_span(&tokens[3]),  (Token::RightBrace,       Position::new(2,  4)));
+        assert_eq!(without_span(&tokens[4]),  (Token::Comma,            Position::new(2,  5)));
+        assert_eq!(without_span(&tokens[5]),  (Token::Dot,              Position::new(2,  6)));
+        assert_eq!(without_span(&tokens[6]),  (Token::Minus,            Position::new(2,  7)));
+        assert_eq!(without_span(&tokens[7]),  (Token::Plus,             Position::new(2,  8)));
+        assert_eq!(without_span(&tokens[8]),  (Token::Colon,            Position::new(2,  9)));
+        assert_eq!(without_span(&tokens[9]),  (Token::Semicolon,        Position::new(2, 10)));
+        assert_eq!(without_span(&tokens[10]), (Token::Star,             Position::new(2, 11)));
+        assert_eq!(without_span(&tokens[11]), (Token::Slash,            Position::new(2, 12)));
+        assert_eq!(without_span(&tokens[12]), (Token::Question,         Position::new(2, 13)));
     }
 
     #[test]
     fn one_or_two_character_tokens() {
         let tokens = test_tokens();
 
-        assert_eq!(tokens[13], TokenMetadata {token: Token::Bang,         position: Position {line: 4, column:  1}});
-        assert_eq!(tokens[14], TokenMetadata {token: Token::BangEqual,    position: Position {line: 4, column:  3}});
-        assert_eq!(tokens[15], TokenMetadata {token: Token::Equal,        position: Position {line: 4, column:  6}});
-        assert_eq!(tokens[16], TokenMetadata {token: Token::EqualEqual,   position: Position {line: 4, column:  8}});
-        assert_eq!(tokens[17], TokenMetadata {token: Token::Greater,      position: Position {line: 4, column: 11}});
-        assert_eq!(tokens[18], TokenMetadata {token: Token::GreaterEqual, position: Position {line: 4, column: 13}});
-        assert_eq!(tokens[19], TokenMetadata {token: Token::Less,         position: Position {line: 4, column: 16}});
-        assert_eq!(tokens[20], TokenMetadata {token: Token::LessEqual,    position: Position {line: 4, column: 18}});
-        assert_eq!(tokens[21], TokenMetadata {token: Token::And,          position: Position {line: 4, column: 21}});
-        assert_eq!(tokens[22], TokenMetadata {token: Token::Or,           position: Position {line: 4, column: 24}});
+        assert_eq!(without_span(&tokens[13]), (Token::Bang,         Position::new(4,  1)));
+        assert_eq!(without_span(&tokens[14]), (Token::BangEqual,    Position::new(4,  3)));
+        assert_eq!(without_span(&tokens[15]), (Token::Equal,        Position::new(4,  6)));
+        assert_eq!(without_span(&tokens[16]), (Token::EqualEqual,   Position::new(4,  8)));
+        assert_eq!(without_span(&tokens[17]), (Token::Greater,      Position::new(4, 11)));
+        assert_eq!(without_span(&tokens[18]), (Token::GreaterEqual, Position::new(4, 13)));
+        assert_eq!(without_span(&tokens[19]), (Token::Less,         Position::new(4, 16)));
+        assert_eq!(without_span(&tokens[20]), (Token::LessEqual,    Position::new(4, 18)));
+        assert_eq!(without_span(&tokens[21]), (Token::And,          Position::new(4, 21)));
+        assert_eq!(without_span(&tokens[22]), (Token::Or,           Position::new(4, 24)));
+    }
+
+    #[test]
+    fn start_positions() {
+        let tokens = "x >= 1".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[1]), (Token::GreaterEqual, Position::new(1, 3)));
+        assert_eq!(tokens[1].end, Position::new(1, 4));
+    }
+
+    #[test]
+    fn end_positions() {
+        let tokens = test_tokens();
+
+        assert_eq!(tokens[24].token, Token::Identifier(Symbol::intern("greeting")));
+        assert_eq!((tokens[24].position, tokens[24].end), (Position::new(6, 5), Position::new(6, 12)));
+
+        let tokens = "x >= \"\"\"a\nb\"\"\"".tokens().unwrap();
+        assert_eq!((tokens[1].position, tokens[1].end), (Position::new(1, 3), Position::new(1, 4)));
+        assert_eq!((tokens[2].position, tokens[2].end), (Position::new(1, 6), Position::new(2, 4)));
     }
 
     #[test]
@@ -312,18 +942,18 @@ mod tests {
         let tokens = test_tokens();
 
         // Identifiers
-        assert_eq!(tokens[24], TokenMetadata {token: Token::Identifier(String::from("greeting")), position: Position {line: 6, column: 5}});
-        assert_eq!(tokens[29], TokenMetadata {token: Token::Identifier(String::from("fraction")), position: Position {line: 7, column: 5}});
-        assert_eq!(tokens[34], TokenMetadata {token: Token::Identifier(String::from("int_eger")),  position: Position {line: 8, column: 5}});
-        assert_eq!(tokens[39], TokenMetadata {token: Token::Identifier(String::from("index")),  position: Position {line: 9, column: 5}});
+        assert_eq!(without_span(&tokens[24]), (Token::Identifier(Symbol::intern("greeting")), Position::new(6, 5)));
+        assert_eq!(without_span(&tokens[29]), (Token::Identifier(Symbol::intern("fraction")), Position::new(7, 5)));
+        assert_eq!(without_span(&tokens[34]), (Token::Identifier(Symbol::intern("int_eger")),  Position::new(8, 5)));
+        assert_eq!(without_span(&tokens[39]), (Token::Identifier(Symbol::intern("index")),  Position::new(9, 5)));
 
         // String literal
-        assert_eq!(tokens[26], TokenMetadata {token: Token::String(String::from("hello")), position: Position {line: 6, column: 16}});
+        assert_eq!(without_span(&tokens[26]), (Token::String(String::from("hello")), Position::new(6, 16)));
 
         // Numbers
-        assert_eq!(tokens[31], TokenMetadata {token: Token::Number(0.5f64), position: Position {line: 7, column: 16}});
-        assert_eq!(tokens[36], TokenMetadata {token: Token::Number(123f64), position: Position {line: 8, column: 16}});
-        assert_eq!(tokens[41], TokenMetadata {token: Token::Number(0f64), position: Position {line: 9, column: 13}});
+        assert_eq!(without_span(&tokens[31]), (Token::Number(0.5f64), Position::new(7, 16)));
+        assert_eq!(without_span(&tokens[36]), (Token::Integer(123), Position::new(8, 16)));
+        assert_eq!(without_span(&tokens[41]), (Token::Integer(0), Position::new(9, 13)));
     }
 
     #[test]
@@ -331,33 +961,624 @@ mod tests {
         let tokens = test_tokens();
 
         // Identifiers
-        assert_eq!(tokens[43], TokenMetadata {token: Token::Class,  position: Position {line: 12, column: 1}});
-        assert_eq!(tokens[44], TokenMetadata {token: Token::Else,   position: Position {line: 12, column: 7}});
-        assert_eq!(tokens[45], TokenMetadata {token: Token::False,  position: Position {line: 12, column: 12}});
-        assert_eq!(tokens[46], TokenMetadata {token: Token::Fn,     position: Position {line: 12, column: 18}});
-        assert_eq!(tokens[47], TokenMetadata {token: Token::For,    position: Position {line: 12, column: 21}});
-        assert_eq!(tokens[48], TokenMetadata {token: Token::If,     position: Position {line: 12, column: 25}});
-        assert_eq!(tokens[49], TokenMetadata {token: Token::Null,   position: Position {line: 12, column: 28}});
-        assert_eq!(tokens[50], TokenMetadata {token: Token::Print,  position: Position {line: 12, column: 33}});
-        assert_eq!(tokens[51], TokenMetadata {token: Token::Return, position: Position {line: 12, column: 39}});
-        assert_eq!(tokens[52], TokenMetadata {token: Token::Super,  position: Position {line: 12, column: 46}});
-        assert_eq!(tokens[53], TokenMetadata {token: Token::This,   position: Position {line: 12, column: 52}});
-        assert_eq!(tokens[54], TokenMetadata {token: Token::True,   position: Position {line: 12, column: 57}});
-        assert_eq!(tokens[55], TokenMetadata {token: Token::Let,    position: Position {line: 12, column: 62}});
-        assert_eq!(tokens[56], TokenMetadata {token: Token::While,  position: Position {line: 12, column: 66}});
+        assert_eq!(without_span(&tokens[43]), (Token::Class,  Position::new(12, 1)));
+        assert_eq!(without_span(&tokens[44]), (Token::Else,   Position::new(12, 7)));
+        assert_eq!(without_span(&tokens[45]), (Token::False,  Position::new(12, 12)));
+        assert_eq!(without_span(&tokens[46]), (Token::Fn,     Position::new(12, 18)));
+        assert_eq!(without_span(&tokens[47]), (Token::For,    Position::new(12, 21)));
+        assert_eq!(without_span(&tokens[48]), (Token::If,     Position::new(12, 25)));
+        assert_eq!(without_span(&tokens[49]), (Token::Null,   Position::new(12, 28)));
+        assert_eq!(without_span(&tokens[50]), (Token::Print,  Position::new(12, 33)));
+        assert_eq!(without_span(&tokens[51]), (Token::Return, Position::new(12, 39)));
+        assert_eq!(without_span(&tokens[52]), (Token::Super,  Position::new(12, 46)));
+        assert_eq!(without_span(&tokens[53]), (Token::This,   Position::new(12, 52)));
+        assert_eq!(without_span(&tokens[54]), (Token::True,   Position::new(12, 57)));
+        assert_eq!(without_span(&tokens[55]), (Token::Let,    Position::new(12, 62)));
+        assert_eq!(without_span(&tokens[56]), (Token::While,  Position::new(12, 66)));
     }
 
     #[test]
     fn eof() {
         let tokens = test_tokens();
 
-        assert_eq!(tokens.last(), Some(&TokenMetadata {token: Token::Eof, position: Position {line: 12, column: 71}}));
+        assert_eq!(tokens.last().map(without_span), Some((Token::Eof, Position::new(12, 71))));
+    }
+
+    #[test]
+    fn loop_keywords() {
+        let tokens = "break continue".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Break, Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Continue, Position::new(1, 7)));
+    }
+
+    #[test]
+    fn custom_keywords() {
+        let mut keywords = default_keywords();
+        keywords.remove("fn");
+        keywords.insert(String::from("func"), Token::Fn);
+
+        let mut scanner = Scanner::with_keywords("func fn", keywords);
+        assert_eq!(scanner.scan_token().unwrap().token, Token::Fn);
+        assert_eq!(scanner.scan_token().unwrap().token, Token::Identifier(Symbol::intern("fn")));
+
+        let tokens: Vec<Token> = TokenStream::with_keywords("FN", [(String::from("FN"), Token::Fn)].into())
+            .map(|token| token.unwrap().token)
+            .collect();
+        assert_eq!(tokens, vec![Token::Fn, Token::Eof]);
+    }
+
+    #[test]
+    fn brackets() {
+        let tokens = "a[0]".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[1]), (Token::LeftBracket, Position::new(1, 2)));
+        assert_eq!(without_span(&tokens[3]), (Token::RightBracket, Position::new(1, 4)));
+    }
+
+    #[test]
+    fn power() {
+        let tokens = "2 ** 3 * 4".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[1]), (Token::StarStar, Position::new(1, 3)));
+        assert_eq!(without_span(&tokens[3]), (Token::Star, Position::new(1, 8)));
+    }
+
+    #[test]
+    fn percent() {
+        let tokens = "7 % 2".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[1]), (Token::Percent, Position::new(1, 3)));
+    }
+
+    #[test]
+    fn bitwise_and_shift() {
+        let tokens = "& | ^ << >>".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Ampersand,  Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Pipe,       Position::new(1, 3)));
+        assert_eq!(without_span(&tokens[2]), (Token::Caret,      Position::new(1, 5)));
+        assert_eq!(without_span(&tokens[3]), (Token::LeftShift,  Position::new(1, 7)));
+        assert_eq!(without_span(&tokens[4]), (Token::RightShift, Position::new(1, 10)));
+    }
+
+    #[test]
+    fn shift_and_comparison_adjacency() {
+        let tokens: Vec<Token> = "<= << <<= >>> &&& |||".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::LessEqual, Token::LeftShift, Token::LeftShift, Token::Equal,
+            Token::UnsignedRightShift, Token::And, Token::Ampersand, Token::Or, Token::Pipe,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn unsigned_right_shift() {
+        let tokens: Vec<Token> = ">>> >> > >>= >= > >>>>".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::UnsignedRightShift, Token::RightShift, Token::Greater,
+            Token::RightShift, Token::Equal, Token::GreaterEqual, Token::Greater,
+            Token::UnsignedRightShift, Token::Greater,
+            Token::Eof,
+        ]);
+
+        let tokens = "a >>> 2".tokens().unwrap();
+        assert_eq!((tokens[1].position, tokens[1].end), (Position::new(1, 3), Position::new(1, 5)));
+    }
+
+    #[test]
+    fn pipeline() {
+        let tokens: Vec<Token> = "|> | || ||>".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::PipeGreater, Token::Pipe, Token::Or, Token::Or, Token::Greater,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn leading_dot() {
+        let tokens: Vec<Token> = ".5 x.y".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::Number(0.5),
+            Token::Identifier(Symbol::intern("x")), Token::Dot, Token::Identifier(Symbol::intern("y")),
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn trailing_dot() {
+        let tokens: Vec<Token> = "1.foo 1.5".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::Integer(1), Token::Dot, Token::Identifier(Symbol::intern("foo")),
+            Token::Number(1.5),
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn dot_after_operand() {
+        let tokens: Vec<Token> = "1..2 x.5 a[0].5 (.5)".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::Integer(1), Token::Dot, Token::Dot, Token::Integer(2),
+            Token::Identifier(Symbol::intern("x")), Token::Dot, Token::Integer(5),
+            Token::Identifier(Symbol::intern("a")), Token::LeftBracket, Token::Integer(0), Token::RightBracket, Token::Dot, Token::Integer(5),
+            Token::LeftParenthesis, Token::Number(0.5), Token::RightParenthesis,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        let tokens: Vec<Token> = "++ + + +++ -- - - ---".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::PlusPlus, Token::Plus, Token::Plus, Token::PlusPlus, Token::Plus,
+            Token::MinusMinus, Token::Minus, Token::Minus, Token::MinusMinus, Token::Minus,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn arrow() {
+        let tokens: Vec<Token> = "-> - > -=>".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::Arrow, Token::Minus, Token::Greater, Token::MinusEqual, Token::Greater,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn question_question() {
+        let tokens: Vec<Token> = "?? ? ? ???".tokens().unwrap().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![
+            Token::QuestionQuestion, Token::Question, Token::Question, Token::QuestionQuestion, Token::Question,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let tokens = "+= -= *= /=".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::PlusEqual,  Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::MinusEqual, Position::new(1, 4)));
+        assert_eq!(without_span(&tokens[2]), (Token::StarEqual,  Position::new(1, 7)));
+        assert_eq!(without_span(&tokens[3]), (Token::SlashEqual, Position::new(1, 10)));
+
+        let tokens = "+ =".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Plus,  Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Equal, Position::new(1, 3)));
+        assert_eq!(tokens[2].token, Token::Eof);
+    }
+
+    #[test]
+    fn spans() {
+        let code = "  123 \"é\"";
+        let tokens = code.tokens().unwrap();
+
+        assert_eq!(tokens[0].span.range(), 2..5);
+        assert_eq!(tokens[1].span.range(), 6..10);
+        assert_eq!(&code[tokens[1].span.range()], "\"é\"");
+        assert_eq!(tokens[2].span.range(), 10..10);
+    }
+
+    #[test]
+    fn single_character_identifier() {
+        let tokens = "x y".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("y")), Position::new(1, 3)));
+    }
+
+    #[test]
+    fn word_operators() {
+        let tokens = "a and b or not c".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[1]), (Token::And,  Position::new(1, 3)));
+        assert_eq!(without_span(&tokens[3]), (Token::Or,   Position::new(1, 9)));
+        assert_eq!(without_span(&tokens[4]), (Token::Bang, Position::new(1, 12)));
+    }
+
+    #[test]
+    fn token_stream() {
+        let code = "let x = (1 + 2) * 3;";
+        let mut stream = TokenStream::new(code);
+
+        for expected in code.tokens().unwrap() {
+            assert_eq!(stream.next().unwrap().unwrap(), expected);
+        }
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn token_stream_stops_on_error() {
+        let mut stream = TokenStream::new("x @ y");
+
+        assert_eq!(stream.next().unwrap().unwrap().token, Token::Identifier(Symbol::intern("x")));
+        assert!(matches!(stream.next(), Some(Err(ScannerError::UnexpectedToken(Position {line: 1, column: 3, ..})))));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn interned_identifiers() {
+        let tokens = "count + count".tokens().unwrap();
+
+        let (Token::Identifier(first), Token::Identifier(second)) = (&tokens[0].token, &tokens[2].token) else {
+            panic!("Expected identifiers")
+        };
+        assert_eq!(first, second);
+        assert_eq!(first.as_str(), "count");
+    }
+
+    #[test]
+    fn multibyte_columns() {
+        let tokens = "\"é\" x".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::String(String::from("é")), Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("x")), Position::new(1, 5)));
+        assert_eq!(tokens[1].span.start(), 5);
+    }
+
+    #[test]
+    fn tab_width() {
+        // Position of the x in the code
+        let position = |code: &str, tab_width| TokenStream::new(code)
+            .with_tab_width(tab_width)
+            .map(Result::unwrap)
+            .find(|token| token.token == Token::Identifier(Symbol::intern("x")))
+            .unwrap()
+            .position;
+
+        assert_eq!(position("\tx", 1), Position::new(1, 2));
+        assert_eq!(position("\tx", 4), Position::new(1, 5));
+        assert_eq!(position("ab\tx", 4), Position::new(1, 5));
+        assert_eq!(position("abcd\tx", 4), Position::new(1, 9));
+        assert_eq!(position("\n\t\tx", 4), Position::new(2, 9));
+    }
+
+    #[test]
+    fn large_input() {
+        let line = "let π = 3.14 * r / 2; // área\n";
+        let expected = line.tokens().unwrap();
+        let tokens = line.repeat(1000).as_str().tokens().unwrap();
+
+        // Every line scans to the same tokens, only on another line
+        assert_eq!(tokens.len(), 1000 * (expected.len() - 1) + 1);
+        for (index, token) in tokens[..tokens.len() - 1].iter().enumerate() {
+            let reference = &expected[index % (expected.len() - 1)];
+            assert_eq!(token.token, reference.token);
+            assert_eq!(token.position, Position::new(index / (expected.len() - 1) + 1, reference.position.column));
+        }
+    }
+
+    #[test]
+    fn template_strings() {
+        let tokens = r#""hi ${name}, ${age + 1}!""#.tokens().unwrap();
+        let Token::TemplateString(parts) = &tokens[0].token else { panic!("Expected a template string") };
+
+        let [TemplatePart::Literal(hi), TemplatePart::Code(name), TemplatePart::Literal(comma), TemplatePart::Code(age), TemplatePart::Literal(bang)] = &parts[..] else {
+            panic!("Expected five parts")
+        };
+        assert_eq!((hi.as_str(), comma.as_str(), bang.as_str()), ("hi ", ", ", "!"));
+        assert_eq!(name.iter().map(without_span).collect::<Vec<_>>(), vec![
+            (Token::Identifier(Symbol::intern("name")), Position::new(1, 7)),
+            (Token::Eof,                                Position::new(1, 11)),
+        ]);
+        assert_eq!(age.iter().map(|token| token.token.clone()).collect::<Vec<_>>(), vec![
+            Token::Identifier(Symbol::intern("age")), Token::Plus, Token::Integer(1), Token::Eof,
+        ]);
+        assert_eq!(tokens[1].token, Token::Eof);
+
+        // Braces and strings nest inside an embedded expression
+        let tokens = r#""${ {"k": "}"}["k"] }""#.tokens().unwrap();
+        assert!(matches!(&tokens[0].token, Token::TemplateString(parts) if matches!(&parts[..], [TemplatePart::Code(code)] if code.len() == 9)));
+
+        assert_eq!("\"plain\"".tokens().unwrap()[0].token, Token::String(String::from("plain")));
+        assert_eq!(r#""\${x} $ {}""#.tokens().unwrap()[0].token, Token::String(String::from("${x} $ {}")));
+
+        assert_eq!(r#""a ${x""#.tokens(), Err(ScannerError::UnterminatedInterpolation(Position::new(1, 4))));
+        assert_eq!(r#"print "a ${x + "#.tokens(), Err(ScannerError::UnterminatedInterpolation(Position::new(1, 10))));
+
+        // Templates nested in templates are limited like nested expressions
+        let nested = |depth: usize| format!("{}1{}", r#""${"#.repeat(depth), r#"}""#.repeat(depth));
+        assert!(nested(MAX_TEMPLATE_DEPTH).as_str().tokens().is_ok());
+        assert_eq!(nested(MAX_TEMPLATE_DEPTH + 1).as_str().tokens(), Err(ScannerError::InterpolationTooDeep(Position::new(1, 3 * MAX_TEMPLATE_DEPTH + 2))));
+        assert!(matches!(nested(3000).as_str().tokens(), Err(ScannerError::InterpolationTooDeep(_))));
+    }
+
+    #[test]
+    fn trivia() {
+        let tokens = "1 // hi\n2".tokens_with_trivia().unwrap();
+        let tokens: Vec<_> = tokens.iter().map(without_span).collect();
+
+        assert_eq!(tokens, vec![
+            (Token::Integer(1),                          Position::new(1, 1)),
+            (Token::Whitespace,                          Position::new(1, 2)),
+            (Token::LineComment(String::from("// hi")),  Position::new(1, 3)),
+            (Token::Whitespace,                          Position::new(1, 8)),
+            (Token::Integer(2),                          Position::new(2, 1)),
+            (Token::Eof,                                 Position::new(2, 2)),
+        ]);
+        assert_eq!("1 // hi\n2".tokens().unwrap().len(), 3);
+
+        // The lexemes of the tokens put together give back the code
+        let code = "let x = 1; /* a\r\n b */\r\n\t# note\r\nprint x;";
+        let tokens = code.tokens_with_trivia().unwrap();
+        let rebuilt: String = tokens.iter().map(|token| &code[token.span.range()]).collect();
+        assert_eq!(rebuilt, code);
+        assert!(tokens.iter().any(|token| token.token == Token::BlockComment(String::from("/* a\r\n b */"))));
+        assert_eq!(tokens.iter().rev().nth(1).map(|token| token.position), Some(Position::new(4, 8)));
+    }
+
+    #[test]
+    fn keyword_suggestions() {
+        let keywords = default_keywords();
+        assert_eq!(suggest_keyword("wile", &keywords).as_deref(), Some("while"));
+        assert_eq!(suggest_keyword("fucn", &keywords).as_deref(), Some("fn"));
+        assert_eq!(suggest_keyword("retrun", &keywords).as_deref(), Some("return"));
+        assert_eq!(suggest_keyword("x", &keywords), None);
+        assert_eq!(suggest_keyword("count", &keywords), None);
+
+        let keywords = [(String::from("mientras"), Token::While)].into();
+        assert_eq!(suggest_keyword("mientas", &keywords).as_deref(), Some("mientras"));
+        assert_eq!(suggest_keyword("wile", &keywords), None);
+    }
+
+    #[test]
+    fn named_source() {
+        let tokens: Vec<_> = TokenStream::new("x").named("script.lhscript").map(Result::unwrap).collect();
+        assert_eq!(tokens[0].position.source(), Some("script.lhscript"));
+        assert_eq!(tokens[0].position.to_string(), "script.lhscript:1:1");
+
+        let err = TokenStream::new("let x;\nx @ y").named("script.lhscript").find_map(Result::err).unwrap();
+        assert_eq!(err.to_string(), "Unexpected token at script.lhscript:2:3");
+        assert_eq!(ScannerError::UnexpectedToken(Position::new(2, 3)).to_string(), "Unexpected token at 2:3");
+    }
+
+    #[test]
+    fn aligned_position() {
+        assert_eq!(format!("{:#}", Position::new(2, 5)), "0002:005");
+        assert_eq!(format!("{:#}", Position::new(120, 42)), "0120:042");
+        assert_eq!(format!("{}", Position::new(2, 5)), "2:5");
+
+        let tokens: Vec<_> = TokenStream::new("x").named("a.lhscript").map(Result::unwrap).collect();
+        assert_eq!(format!("{:#}", tokens[0].position), "a.lhscript:0001:001");
+    }
+
+    #[test]
+    fn collect_errors() {
+        let errors = "let x = @;\nprint x $ 2;".tokens_collect().unwrap_err();
+        assert_eq!(errors, vec![
+            ScannerError::UnexpectedToken(Position::new(1, 9)),
+            ScannerError::UnexpectedToken(Position::new(2, 9)),
+        ]);
+
+        let errors = "1 @ \"unterminated".tokens_collect().unwrap_err();
+        assert_eq!(errors, vec![
+            ScannerError::UnexpectedToken(Position::new(1, 3)),
+            ScannerError::UnterminatedString(Position::new(1, 5)),
+        ]);
+
+        assert_eq!("let x = 1;".tokens_collect(), Ok("let x = 1;".tokens().unwrap()));
+    }
+
+    #[test]
+    fn token_stream_reset() {
+        let snippets = ["let x = 1;", "x @ y", "// comment\nprint \"a\";", ""];
+        let mut stream = TokenStream::new("");
+
+        for code in snippets {
+            stream.reset_with(code);
+            let mut fresh = TokenStream::new(code);
+
+            let reused_tokens: Vec<_> = stream.by_ref().map(|r| r.map_err(|e| e.to_string())).collect();
+            let fresh_tokens: Vec<_> = fresh.by_ref().map(|r| r.map_err(|e| e.to_string())).collect();
+
+            assert_eq!(reused_tokens, fresh_tokens);
+            assert_eq!(stream.comments(), fresh.comments());
+        }
+    }
+
+    #[test]
+    fn comment_spans() {
+        let mut stream = TokenStream::new("x // line\n/* block */ y");
+        for token in stream.by_ref() {
+            token.unwrap();
+        }
+
+        let comments: Vec<Range<usize>> = stream.comments().iter().map(Span::range).collect();
+        assert_eq!(comments, vec![2..9, 10..21]);
+    }
+
+    #[test]
+    fn first_line() {
+        assert_eq!(Scanner::new("(").position, Position::new(1, 0));
+
+        let tokens = "(".tokens().unwrap();
+        assert_eq!(without_span(&tokens[0]), (Token::LeftParenthesis, Position::new(1, 1)));
+    }
+
+    #[test]
+    fn scientific_notation() {
+        let tokens = "1.5e10 2E-3".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Number(1.5e10), Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Number(2e-3),   Position::new(1, 8)));
+    }
+
+    #[test]
+    fn dangling_exponent() {
+        let result = "1e".tokens();
+
+        assert!(matches!(result, Err(ScannerError::NumberLiteralParsingError(Position {line: 1, column: 1, ..}))));
+    }
+
+    #[test]
+    fn digit_separators() {
+        let tokens = "1_000_000 1.234_567 1e1_0".tokens().unwrap();
+
+        assert_eq!(tokens[0].token, Token::Integer(1_000_000));
+        assert_eq!(tokens[1].token, Token::Number(1.234_567f64));
+        assert_eq!(tokens[2].token, Token::Number(1e10f64));
+    }
+
+    #[test]
+    fn misplaced_digit_separators() {
+        for code in ["_5", "5_", "1__0", "1_.5", "1._5", "1e_5"] {
+            assert!(
+                matches!(code.tokens(), Err(ScannerError::NumberLiteralParsingError(Position {line: 1, column: 1, ..}))),
+                "{} should not scan", code
+            );
+        }
+    }
+
+    #[test]
+    fn multiline_string() {
+        let tokens = "\"\"\"first\nsecond\"\"\" x".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::String(String::from("first\nsecond")), Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("x")),       Position::new(2, 11)));
+    }
+
+    #[test]
+    fn newline_in_string() {
+        let result = "\"first\nsecond\"".tokens();
+
+        assert!(matches!(result, Err(ScannerError::NewlineInString(_))));
+    }
+
+    #[test]
+    fn string_continuation() {
+        let tokens = "\"abc\\\ndef\" x".tokens().unwrap();
+
+        assert_eq!(tokens[0].token, Token::String(String::from("abcdef")));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("x")), Position::new(2, 6)));
+
+        let tokens = "\"\"\"a\\\nb\"\"\" x".tokens().unwrap();
+        assert_eq!(tokens[0].token, Token::String(String::from("ab")));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("x")), Position::new(2, 6)));
+
+        let tokens = "\"\"\"a\\\r\nb\"\"\"".tokens().unwrap();
+        assert_eq!(tokens[0].token, Token::String(String::from("ab")));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert!(matches!("\"abc".tokens(), Err(ScannerError::UnterminatedString(Position {line: 1, column: 1, ..}))));
+        assert!(matches!("\"\"\"abc\"\"".tokens(), Err(ScannerError::UnterminatedString(Position {line: 1, column: 1, ..}))));
+    }
+
+    #[test]
+    fn hash_comment() {
+        let tokens = "# comment\nx".tokens().unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(2, 1)));
+
+        let tokens = "#\nx".tokens().unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(2, 1)));
+
+        let tokens = "#\r\nx".tokens().unwrap();
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(2, 1)));
+
+        let tokens: Vec<Token> = "x #".tokens().unwrap().into_iter().map(|t| t.token).collect();
+        assert_eq!(tokens, vec![Token::Identifier(Symbol::intern("x")), Token::Eof]);
+    }
+
+    #[test]
+    fn windows_line_endings() {
+        let mut stream = TokenStream::new("x // comment\r\ny\r\n\r\nz");
+        let tokens: Vec<TokenMetadata> = stream.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Identifier(Symbol::intern("y")), Position::new(2, 1)));
+        assert_eq!(without_span(&tokens[2]), (Token::Identifier(Symbol::intern("z")), Position::new(4, 1)));
+
+        // The comment ends before the line ending
+        let comments: Vec<Range<usize>> = stream.comments().iter().map(Span::range).collect();
+        assert_eq!(comments, vec![2..12]);
+
+        let tokens = "\"ab\\\r\ncd\" \"\"\"a\r\nb\"\"\" x".tokens().unwrap();
+        assert_eq!(tokens[0].token, Token::String(String::from("abcd")));
+        assert_eq!(tokens[1].token, Token::String(String::from("a\nb")));
+        assert_eq!(without_span(&tokens[2]), (Token::Identifier(Symbol::intern("x")), Position::new(3, 6)));
+    }
+
+    #[test]
+    fn nested_multiline() {
+        let tokens = "/* outer /* inner */ still comment */ x".tokens().unwrap();
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(without_span(&tokens[0]), (Token::Identifier(Symbol::intern("x")), Position::new(1, 39)));
+    }
+
+    #[test]
+    fn unterminated_nested_multiline() {
+        let result = "x /* outer\n /* inner */ no end".tokens();
+
+        assert!(matches!(result, Err(ScannerError::UnterminatedMultilineComment(Position {line: 1, column: 3, ..}))));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let tokens = r#""tab\tquote\"slash\\""#.tokens().unwrap();
+
+        assert_eq!(tokens[0].token, Token::String(String::from("tab\tquote\"slash\\")));
+        assert!(matches!(r#""bad \q""#.tokens(), Err(ScannerError::InvalidEscapeSequence(Position {line: 1, column: 6, ..}))));
+    }
+
+    #[test]
+    fn invalid_escape_skips_rest_of_string() {
+        let (tokens, errors) = r#"print "bad \q and \w" + 1;"#.tokens_partial();
+
+        assert!(matches!(errors[..], [ScannerError::InvalidEscapeSequence(Position {line: 1, column: 12, ..})]));
+        assert_eq!(tokens.iter().map(|t| &t.token).collect::<Vec<_>>(), [&Token::Print, &Token::Plus, &Token::Integer(1), &Token::Semicolon, &Token::Eof]);
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        let tokens = r#""caf\u{e9} \u{1F600}\n" '\u{41}'"#.tokens().unwrap();
+        assert_eq!(tokens[0].token, Token::String(String::from("café 😀\n")));
+        assert_eq!(tokens[1].token, Token::Char('A'));
+
+        for code in [r#""\u{GG}""#, r#""\u{}""#, r#""\u41""#, r#""\u{1234567}""#, r#""\u{D800}""#, r#""\u{110000}""#] {
+            assert!(
+                matches!(code.tokens(), Err(ScannerError::InvalidUnicodeEscape(Position {line: 1, column: 2, ..}))),
+                "{} should not scan", code,
+            );
+        }
+    }
+
+    #[test]
+    fn char_literals() {
+        let tokens = r"'a' '\n' '\''".tokens().unwrap();
+
+        assert_eq!(without_span(&tokens[0]), (Token::Char('a'),  Position::new(1, 1)));
+        assert_eq!(without_span(&tokens[1]), (Token::Char('\n'), Position::new(1, 5)));
+        assert_eq!(without_span(&tokens[2]), (Token::Char('\''), Position::new(1, 10)));
+    }
+
+    #[test]
+    fn invalid_char_literals() {
+        assert!(matches!("''".tokens(),   Err(ScannerError::InvalidCharLiteral(Position {line: 1, column: 1, ..}))));
+        assert!(matches!("'ab'".tokens(), Err(ScannerError::InvalidCharLiteral(Position {line: 1, column: 1, ..}))));
+        assert!(matches!("'a".tokens(),   Err(ScannerError::InvalidCharLiteral(Position {line: 1, column: 1, ..}))));
     }
 
     #[test]
-    #[should_panic]
     fn bad_multiline() {
-        let _tokens = "/* Bad multiline comment without termination".tokens().unwrap();
+        let result = "let x;\n  /* Bad multiline comment without termination".tokens();
+
+        assert_eq!(result, Err(ScannerError::UnterminatedMultilineComment(Position::new(2, 3))));
     }
 
 }
@@ -0,0 +1,69 @@
+use std::ops::Range;
+use crate::{errors::ScannerError, scanner::TokenStream, token::Token};
+
+/// Kind of a lexeme, as needed by an editor for semantic highlighting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SemanticKind {
+    Keyword,
+    String,
+    Number,
+    Operator,
+    Comment,
+    Identifier,
+}
+
+/// Byte ranges and kinds of all lexemes, including comments, in a string of code,
+/// ordered by their position
+pub fn semantic_tokens(code: &str) -> Result<Vec<(Range<usize>, SemanticKind)>, ScannerError> {
+    let mut stream = TokenStream::new(code);
+
+    let mut tokens = vec![];
+    for token in stream.by_ref() {
+        let token = token?;
+        if let Some(kind) = semantic_kind(&token.token) {
+            tokens.push((token.span.range(), kind));
+        }
+    }
+
+    tokens.extend(stream.comments().iter().map(|span| (span.range(), SemanticKind::Comment)));
+    tokens.sort_by_key(|(range, _)| range.start);
+
+    Ok(tokens)
+}
+
+/// Semantic kind of a token, None for tokens without a lexeme
+fn semantic_kind(token: &Token) -> Option<SemanticKind> {
+    Some(match token {
+        Token::Identifier(_) => SemanticKind::Identifier,
+        Token::String(_) | Token::TemplateString(_) | Token::Char(_) => SemanticKind::String,
+        Token::Number(_) | Token::Integer(_) => SemanticKind::Number,
+        Token::Assert | Token::Break | Token::Class | Token::Continue | Token::Else | Token::False | Token::Fn | Token::For | Token::If |
+        Token::Include | Token::Null | Token::Print | Token::Return | Token::Super | Token::This |
+        Token::True | Token::Let | Token::While => SemanticKind::Keyword,
+        Token::LineComment(_) | Token::BlockComment(_) => SemanticKind::Comment,
+        Token::Whitespace | Token::Eof => return None,
+        _ => SemanticKind::Operator,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_and_ranges() {
+        let tokens = semantic_tokens("let x = \"hi\" + 1; // note").unwrap();
+
+        assert_eq!(tokens, vec![
+            (0..3,   SemanticKind::Keyword),
+            (4..5,   SemanticKind::Identifier),
+            (6..7,   SemanticKind::Operator),
+            (8..12,  SemanticKind::String),
+            (13..14, SemanticKind::Operator),
+            (15..16, SemanticKind::Number),
+            (16..17, SemanticKind::Operator),
+            (18..25, SemanticKind::Comment),
+        ]);
+    }
+}
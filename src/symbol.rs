@@ -0,0 +1,105 @@
+use std::{collections::HashMap, fmt::{Debug, Display}, sync::{Mutex, OnceLock}};
+
+/// An interned string, like the name of an identifier. Symbols of equal
+/// strings are equal, so comparing them is comparing two integers
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Storage of every interned string, each one only stored once
+#[derive(Default)]
+pub struct StringInterner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl StringInterner {
+    /// Get the symbol of a string, storing it if it's the first occurrence
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(string) {
+            return symbol;
+        }
+
+        // Interned strings live as long as the program, so symbols can hand
+        // out plain references to them
+        let string: &'static str = Box::leak(Box::from(string));
+        let symbol = Symbol(self.strings.len() as u32);
+        self.strings.push(string);
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    /// Get the string a symbol was interned from
+    pub fn resolve(&self, symbol: Symbol) -> &'static str {
+        self.strings[symbol.0 as usize]
+    }
+}
+
+/// The interner shared by the whole process, so a symbol interned on one
+/// thread resolves on any other
+fn interner() -> std::sync::MutexGuard<'static, StringInterner> {
+    static INTERNER: OnceLock<Mutex<StringInterner>> = OnceLock::new();
+
+    // The interner is never left half updated, so a panic while holding
+    // the lock doesn't make it unusable
+    INTERNER.get_or_init(Mutex::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Symbol {
+    /// Intern a string in the interner of the process
+    pub fn intern(string: &str) -> Self {
+        interner().intern(string)
+    }
+
+    /// The string this symbol was interned from
+    pub fn as_str(&self) -> &'static str {
+        interner().resolve(*self)
+    }
+}
+
+/// Debug output shows the text rather than the index, like in token dumps
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern() {
+        let mut interner = StringInterner::default();
+        let x = interner.intern("x");
+        let y = interner.intern("y");
+
+        assert_eq!(interner.intern("x"), x);
+        assert_ne!(x, y);
+        assert_eq!(interner.resolve(y), "y");
+    }
+
+    #[test]
+    fn symbol() {
+        assert_eq!(Symbol::intern("count"), Symbol::intern("count"));
+        assert_eq!(Symbol::intern("count").as_str(), "count");
+        assert_eq!(Symbol::intern("count").to_string(), "count");
+        assert_eq!(format!("{:?}", Symbol::intern("count")), "\"count\"");
+    }
+
+    #[test]
+    fn across_threads() {
+        let symbol = std::thread::spawn(|| Symbol::intern("only interned on another thread")).join().unwrap();
+        assert_eq!(symbol.as_str(), "only interned on another thread");
+        assert_eq!(Symbol::intern("only interned on another thread"), symbol);
+
+        let tokens = std::thread::spawn(|| crate::scanner::TokenStream::new("x @").named("script.lhscript").collect::<Vec<_>>()).join().unwrap();
+        assert_eq!(tokens[1].as_ref().unwrap_err().to_string(), "Unexpected token at script.lhscript:1:3");
+    }
+}
@@ -1,4 +1,6 @@
-use crate::scanner::Position;
+use crate::scanner::{Position, Span};
+use crate::symbol::Symbol;
+use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
@@ -7,6 +9,8 @@ pub enum Token {
     RightParenthesis,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -15,6 +19,7 @@ pub enum Token {
     Semicolon,
     Slash,
     Star,
+    Percent,
     Question,
 
     // One or two character tokens
@@ -28,19 +33,43 @@ pub enum Token {
     LessEqual,
     And,
     Or,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    Ampersand,
+    Pipe,
+    Caret,
+    LeftShift,
+    RightShift,
+    UnsignedRightShift,
+    PipeGreater,
+    StarStar,
+    Arrow,
+    PlusPlus,
+    MinusMinus,
+    QuestionQuestion,
 
     //Literals
-    Identifier(String),
+    Identifier(Symbol),
     String(String),
+    /// A string literal with embedded expressions, e.g. "x is ${x}"
+    TemplateString(Vec<TemplatePart>),
+    Char(char),
     Number(f64),
+    Integer(i64),
 
     // Keywords
+    Assert,
+    Break,
     Class,
+    Continue,
     Else,
     False,
     Fn,
     For,
     If,
+    Include,
     Null,
     Print,
     Return,
@@ -50,11 +79,152 @@ pub enum Token {
     Let,
     While,
 
+    // Trivia, only scanned when asked for, see TokenStream::with_trivia
+    LineComment(String),
+    BlockComment(String),
+    Whitespace,
+
     Eof,
 }
 
-#[derive(Debug, PartialEq)]
+/// A piece of a template string, either text or the tokens of an embedded
+/// expression, ending with an Eof-token at the closing brace
+#[derive(Debug, PartialEq, Clone)]
+pub enum TemplatePart {
+    Literal(String),
+    Code(Vec<TokenMetadata>),
+}
+
+/// The token as it is written in the code, literals with their value
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lexeme = match self {
+            Self::LeftParenthesis => "(",
+            Self::RightParenthesis => ")",
+            Self::LeftBrace => "{",
+            Self::RightBrace => "}",
+            Self::LeftBracket => "[",
+            Self::RightBracket => "]",
+            Self::Comma => ",",
+            Self::Dot => ".",
+            Self::Minus => "-",
+            Self::Plus => "+",
+            Self::Colon => ":",
+            Self::Semicolon => ";",
+            Self::Slash => "/",
+            Self::Star => "*",
+            Self::Percent => "%",
+            Self::Question => "?",
+            Self::QuestionQuestion => "??",
+            Self::Bang => "!",
+            Self::BangEqual => "!=",
+            Self::Equal => "=",
+            Self::EqualEqual => "==",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::PlusEqual => "+=",
+            Self::MinusEqual => "-=",
+            Self::StarEqual => "*=",
+            Self::SlashEqual => "/=",
+            Self::Ampersand => "&",
+            Self::Pipe => "|",
+            Self::Caret => "^",
+            Self::LeftShift => "<<",
+            Self::RightShift => ">>",
+            Self::UnsignedRightShift => ">>>",
+            Self::PipeGreater => "|>",
+            Self::StarStar => "**",
+            Self::Arrow => "->",
+            Self::PlusPlus => "++",
+            Self::MinusMinus => "--",
+            Self::Class => "class",
+            Self::Else => "else",
+            Self::False => "false",
+            Self::Fn => "fn",
+            Self::For => "for",
+            Self::If => "if",
+            Self::Null => "null",
+            Self::Print => "print",
+            Self::Assert => "assert",
+            Self::Include => "include",
+            Self::Return => "return",
+            Self::Break => "break",
+            Self::Continue => "continue",
+            Self::Super => "super",
+            Self::This => "this",
+            Self::True => "true",
+            Self::Let => "let",
+            Self::While => "while",
+            Self::Identifier(name) => return write!(f, "{}", name),
+            Self::String(s) => return write!(f, "\"{}\"", s),
+            Self::TemplateString(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        TemplatePart::Literal(text) => write!(f, "{}", text)?,
+                        TemplatePart::Code(tokens) => {
+                            let code: Vec<String> = tokens.iter()
+                                .filter(|token| token.token != Self::Eof)
+                                .map(|token| token.token.to_string())
+                                .collect();
+                            write!(f, "${{{}}}", code.join(" "))?;
+                        },
+                    }
+                }
+                return write!(f, "\"");
+            },
+            Self::Char(c) => return write!(f, "'{}'", c),
+            Self::Number(n) => return write!(f, "{}", n),
+            Self::Integer(n) => return write!(f, "{}", n),
+            Self::LineComment(text) | Self::BlockComment(text) => return write!(f, "{}", text),
+            Self::Whitespace => "whitespace",
+            Self::Eof => "end of file",
+        };
+
+        write!(f, "{}", lexeme)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct TokenMetadata {
     pub token: Token,
     pub position: Position,
+
+    /// Position of the last character of the lexeme
+    pub end: Position,
+    pub span: Span,
+}
+
+impl TokenMetadata {
+    /// A token that wasn't scanned from any code, like one in a hand built AST
+    pub fn synthetic(token: Token) -> Self {
+        TokenMetadata { token, position: Position::default(), end: Position::default(), span: Span::default() }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(Token::Plus.to_string(), "+");
+        assert_eq!(Token::UnsignedRightShift.to_string(), ">>>");
+        assert_eq!(Token::While.to_string(), "while");
+        assert_eq!(Token::Identifier(Symbol::intern("count")).to_string(), "count");
+        assert_eq!(Token::String(String::from("hi")).to_string(), "\"hi\"");
+        assert_eq!(Token::Char('c').to_string(), "'c'");
+        assert_eq!(Token::TemplateString(vec![
+            TemplatePart::Literal(String::from("x is ")),
+            TemplatePart::Code(vec![TokenMetadata::synthetic(Token::Identifier(Symbol::intern("x"))), TokenMetadata::synthetic(Token::Eof)]),
+        ]).to_string(), "\"x is ${x}\"");
+        assert_eq!(Token::Number(1.5).to_string(), "1.5");
+        assert_eq!(Token::Integer(42).to_string(), "42");
+        assert_eq!(Token::Eof.to_string(), "end of file");
+    }
 }
@@ -0,0 +1,353 @@
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, fmt::{Debug, Display}, rc::Rc};
+use crate::{ast::Statement, environment::Environment, errors::{RuntimeError, ScriptError}, scanner::Position};
+
+/// A runtime value produced by evaluating an expression
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    Integer(i64),
+    Str(String),
+    Bool(bool),
+    Null,
+    Function(Rc<Function>),
+    NativeFunction(Rc<NativeFunction>),
+    Array(Vec<Value>),
+    Map(HashMap<Key, Value>),
+}
+
+/// A value usable as a map key. Whole floats become integer keys, so 1 and
+/// 1.0 are the same key, like they are equal values
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+/// Only strings, whole numbers and booleans can be keys
+impl TryFrom<Value> for Key {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Str(s) => Ok(Key::Str(s)),
+            Value::Integer(n) => Ok(Key::Int(n)),
+            Value::Number(n) if n.fract() == 0f64 && (i64::MIN as f64..i64::MAX as f64).contains(&n) => Ok(Key::Int(n as i64)),
+            Value::Bool(b) => Ok(Key::Bool(b)),
+            _ => Err(RuntimeError::InvalidKey),
+        }
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Int(n) => write!(f, "{}", n),
+            Self::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// A user defined function together with the environment it was declared
+/// in, so it can reach the variables around it when called later
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Statement>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+/// Functions are only equal to themselves
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// The closure is left out, it may well contain the function itself
+impl Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// A built-in function implemented in Rust
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub function: fn(&[Value]) -> Result<Value, ScriptError>,
+}
+
+/// Natives are only equal to themselves
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+/// Integers and floats are equal when they hold the same number, other
+/// values are only equal to values of the same type
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(l), Self::Number(r)) => l == r,
+            (Self::Integer(l), Self::Integer(r)) => l == r,
+            // Compared as i128, which holds both exactly
+            (Self::Integer(i), Self::Number(n)) | (Self::Number(n), Self::Integer(i)) => n.fract() == 0f64 && *n as i128 == *i as i128,
+            (Self::Str(l), Self::Str(r)) => l == r,
+            (Self::Bool(l), Self::Bool(r)) => l == r,
+            (Self::Null, Self::Null) => true,
+            (Self::Function(l), Self::Function(r)) => l == r,
+            (Self::NativeFunction(l), Self::NativeFunction(r)) => l == r,
+            (Self::Array(l), Self::Array(r)) => l == r,
+            (Self::Map(l), Self::Map(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+/// Numbers are ordered by value, integers and floats alike, and strings
+/// lexically. Other values are only ordered against equal values
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Number(l), Self::Number(r)) => l.partial_cmp(r),
+            (Self::Integer(l), Self::Integer(r)) => Some(l.cmp(r)),
+            (Self::Integer(i), Self::Number(n)) => mixed_cmp(*i, *n),
+            (Self::Number(n), Self::Integer(i)) => mixed_cmp(*i, *n).map(Ordering::reverse),
+            (Self::Str(l), Self::Str(r)) => Some(l.cmp(r)),
+            _ if self == other => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+/// Order an integer against a float, exactly where the float rounding of the
+/// integer can't tell them apart
+fn mixed_cmp(i: i64, n: f64) -> Option<Ordering> {
+    match (i as f64).partial_cmp(&n)? {
+        Ordering::Equal => Some((i as i128).cmp(&(n as i128))),
+        ordering => Some(ordering),
+    }
+}
+
+/// Significant digits numbers are printed with, a few less than an f64
+/// holds, so 0.1 + 0.2 prints as 0.3
+const SIGNIFICANT_DIGITS: usize = 15;
+
+/// Magnitudes printed in plain decimal notation, scientific outside of them
+const PLAIN_NUMBERS: std::ops::Range<f64> = 1e-6..1e16;
+
+/// Print a float the same way whatever the locale, always with a '.' as
+/// decimal point, without trailing zeros and in scientific notation when
+/// very large or very small, e.g. 1e20 or 1.5e-7
+fn format_number(n: f64) -> String {
+    if !n.is_finite() {
+        return n.to_string();
+    }
+    if n == 0.0 {
+        return String::from("0");
+    }
+
+    if !PLAIN_NUMBERS.contains(&n.abs()) {
+        let formatted = format!("{:.*e}", SIGNIFICANT_DIGITS - 1, n);
+        let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+        return format!("{}e{}", trim_fraction(mantissa), exponent);
+    }
+
+    // Enough decimals for the significant digits, trailing zeros are trimmed
+    let magnitude = n.abs().log10().floor() as i32;
+    let decimals = (SIGNIFICANT_DIGITS as i32 - 1 - magnitude).max(0) as usize;
+    trim_fraction(&format!("{:.*}", decimals, n)).to_string()
+}
+
+/// Drop trailing zeros of the fraction of a formatted number, and the
+/// decimal point if nothing is left after it
+fn trim_fraction(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+impl Value {
+    /// Only null and false are falsey, everything else is truthy
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Null | Value::Bool(false))
+    }
+
+    /// Equality as seen by == and !=, values of different types are simply
+    /// unequal, apart from integers and floats
+    pub fn equals(&self, other: &Value) -> bool {
+        self == other
+    }
+
+    /// Order two values for <, <=, > and >=. Only two numbers or two strings
+    /// can be compared, anything else fails at the operator's position. NaN
+    /// is unordered, so every comparison with it is false
+    pub fn compare(&self, other: &Value, position: Position) -> Result<Option<Ordering>, RuntimeError> {
+        match (self, other) {
+            (Self::Number(_) | Self::Integer(_), Self::Number(_) | Self::Integer(_))
+            | (Self::Str(_), Self::Str(_)) => Ok(self.partial_cmp(other)),
+            _ => Err(RuntimeError::OperandsMustBeNumbersOrStrings(position)),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", format_number(*n)),
+            Self::Integer(n) => write!(f, "{}", n),
+            Self::Str(s) => write!(f, "{}", s),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Null => write!(f, "null"),
+            Self::Function(function) => write!(f, "<fn {}>", function.name),
+            Self::NativeFunction(function) => write!(f, "<native fn {}>", function.name),
+            Self::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            },
+            Self::Map(entries) => {
+                // Sorted by key, so the output doesn't depend on the hashing
+                let mut keys: Vec<&Key> = entries.keys().collect();
+                keys.sort();
+
+                write!(f, "{{")?;
+                for (index, key) in keys.into_iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, entries[key])?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_display() {
+        let display = |n: f64| Value::Number(n).to_string();
+
+        assert_eq!(display(42.0), "42");
+        assert_eq!(display(-0.0), "0");
+        assert_eq!(display(0.1 + 0.2), "0.3");
+        assert_eq!(display(123456.789), "123456.789");
+        assert_eq!(display(1.0 / 3.0), "0.333333333333333");
+        assert_eq!(display(1e15), "1000000000000000");
+        assert_eq!(display(1e20), "1e20");
+        assert_eq!(display(-2.5e300), "-2.5e300");
+        assert_eq!(display(1.5e-7), "1.5e-7");
+        assert_eq!(display(f64::INFINITY), "inf");
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Value::Number(3f64).to_string(), "3");
+        assert_eq!(Value::Number(-0.5f64).to_string(), "-0.5");
+        assert_eq!(Value::Str(String::from("hi")).to_string(), "hi");
+        assert_eq!(Value::Bool(true).to_string(), "true");
+        assert_eq!(Value::Bool(false).to_string(), "false");
+        assert_eq!(Value::Null.to_string(), "null");
+        assert_eq!(Value::Array(vec![Value::Number(1f64), Value::Str(String::from("a"))]).to_string(), "[1, a]");
+        let map = HashMap::from([(Key::Str(String::from("b")), Value::Null), (Key::Str(String::from("a")), Value::Number(1f64))]);
+        assert_eq!(Value::Map(map).to_string(), "{a: 1, b: null}");
+        let map = HashMap::from([(Key::Int(2), Value::Null), (Key::Bool(true), Value::Integer(1))]);
+        assert_eq!(Value::Map(map).to_string(), "{true: 1, 2: null}");
+
+        let function = Function {
+            name: String::from("f"),
+            params: vec![],
+            body: vec![],
+            closure: Rc::new(RefCell::new(Environment::new())),
+        };
+        assert_eq!(Value::Function(Rc::new(function)).to_string(), "<fn f>");
+    }
+
+    #[test]
+    fn numeric_equality() {
+        assert_eq!(Value::Integer(1), Value::Number(1f64));
+        assert_ne!(Value::Integer(1), Value::Number(1.5f64));
+        assert_ne!(Value::Integer(9007199254740993), Value::Number(9007199254740992f64));
+        assert_ne!(Value::Integer(i64::MAX), Value::Number(i64::MAX as f64));
+        assert_ne!(Value::Integer(0), Value::Number(f64::NAN));
+    }
+
+    #[test]
+    fn equals() {
+        assert!(Value::Str(String::from("a")).equals(&Value::Str(String::from("a"))));
+        assert!(Value::Integer(2).equals(&Value::Number(2f64)));
+        assert!(Value::Null.equals(&Value::Null));
+        assert!(!Value::Null.equals(&Value::Bool(false)));
+        assert!(!Value::Str(String::from("1")).equals(&Value::Integer(1)));
+    }
+
+    #[test]
+    fn ordering() {
+        assert_eq!(Value::Integer(1).partial_cmp(&Value::Integer(2)), Some(Ordering::Less));
+        assert_eq!(Value::Number(2.5f64).partial_cmp(&Value::Integer(2)), Some(Ordering::Greater));
+        assert_eq!(Value::Integer(9007199254740993).partial_cmp(&Value::Number(9007199254740992f64)), Some(Ordering::Greater));
+        assert_eq!(Value::Number(i64::MAX as f64).partial_cmp(&Value::Integer(i64::MAX)), Some(Ordering::Greater));
+        assert_eq!(Value::Number(f64::NAN).partial_cmp(&Value::Integer(0)), None);
+        assert_eq!(Value::Str(String::from("abc")).partial_cmp(&Value::Str(String::from("abd"))), Some(Ordering::Less));
+        assert_eq!(Value::Null.partial_cmp(&Value::Null), Some(Ordering::Equal));
+        assert_eq!(Value::Null.partial_cmp(&Value::Integer(0)), None);
+        assert_eq!(Value::Str(String::from("1")).partial_cmp(&Value::Integer(1)), None);
+    }
+
+    #[test]
+    fn compare() {
+        let position = Position::new(1, 3);
+
+        assert_eq!(Value::Str(String::from("b")).compare(&Value::Str(String::from("a")), position).unwrap(), Some(Ordering::Greater));
+        assert_eq!(Value::Integer(1).compare(&Value::Number(f64::NAN), position).unwrap(), None);
+        assert!(matches!(Value::Str(String::from("a")).compare(&Value::Integer(1), position), Err(RuntimeError::OperandsMustBeNumbersOrStrings(_))));
+        assert!(matches!(Value::Null.compare(&Value::Null, position), Err(RuntimeError::OperandsMustBeNumbersOrStrings(_))));
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(Key::try_from(Value::Number(2f64)).unwrap(), Key::Int(2));
+        assert_eq!(Key::try_from(Value::Integer(2)).unwrap(), Key::Int(2));
+        assert_eq!(Key::try_from(Value::Bool(false)).unwrap(), Key::Bool(false));
+        assert!(matches!(Key::try_from(Value::Number(0.5f64)), Err(RuntimeError::InvalidKey)));
+        assert!(matches!(Key::try_from(Value::Number(f64::INFINITY)), Err(RuntimeError::InvalidKey)));
+        assert!(matches!(Key::try_from(Value::Null), Err(RuntimeError::InvalidKey)));
+    }
+
+    #[test]
+    fn truthiness() {
+        assert!(!Value::Null.is_truthy());
+        assert!(!Value::Bool(false).is_truthy());
+        assert!(Value::Bool(true).is_truthy());
+        assert!(Value::Number(0f64).is_truthy());
+        assert!(Value::Integer(0).is_truthy());
+        assert!(Value::Str(String::new()).is_truthy());
+    }
+}
@@ -0,0 +1,47 @@
+use std::{io::Write, process::{Command, Stdio}};
+
+/// Run the interpreter binary on a script file, returning stdout and the
+/// exit code
+fn run_file(name: &str, script: &str) -> (String, Option<i32>) {
+    let path = std::env::temp_dir().join(format!("lhscript-{}-{}.lhs", name, std::process::id()));
+    std::fs::write(&path, script).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lhscript")).arg(&path).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    (String::from_utf8(output.stdout).unwrap(), output.status.code())
+}
+
+#[test]
+fn only_script_output() {
+    assert_eq!(run_file("print", "print 1 + 2;"), (String::from("3\n"), Some(0)));
+    assert_eq!(run_file("empty", "let x = 1;"), (String::new(), Some(0)));
+}
+
+#[test]
+fn eval() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lhscript")).args(["-e", "print 1 + 2;"]).output().unwrap();
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn error_status() {
+    assert_eq!(run_file("parse", "print 1 +;").1, Some(65));
+    assert_eq!(run_file("resolve", "print x;").1, Some(65));
+    assert_eq!(run_file("runtime", "print 1 / 0;").1, Some(70));
+}
+
+#[test]
+fn prompt() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lhscript"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"print 1;\nexit\n").unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), ">>> 1\n>>> ");
+}